@@ -0,0 +1,53 @@
+use crate::crane::Crane;
+use crate::{Move, Stacks};
+use std::io::Write;
+use std::time::Duration;
+
+fn show(stacks: &Stacks, title: &str, moved: Option<&Move>) {
+    // The crates which were just moved are the ones now on top of the destination stack.
+    let highlight = |slot: usize, level: usize| -> bool {
+        let Some(mv) = moved else { return false; };
+        let to = stacks.slot(mv.to).unwrap();
+        return slot == to && level + mv.count >= stacks.stacks[to].len();
+    };
+
+    // Clear the screen and move the cursor to the top left before each frame.
+    let mut out = std::io::stdout().lock();
+    write!(out, "\x1b[2J\x1b[H").unwrap();
+    writeln!(out, "{}", title).unwrap();
+    writeln!(out).unwrap();
+    for row in stacks.draw(highlight) { writeln!(out, "{}", row).unwrap(); }
+    out.flush().unwrap();
+}
+
+// Play |moves| back with |crane|, redrawing the stacks and pausing for |delay| after each one. The
+// first |jump| moves are carried out without being shown.
+pub fn run(
+    stacks: &Stacks,
+    moves: &[(usize, Move)],
+    crane: &dyn Crane,
+    delay: Duration,
+    jump: usize,
+) -> Result<(), (usize, String)> {
+    let mut stacks = stacks.clone();
+    let total = moves.len();
+    let jump = std::cmp::min(jump, total);
+
+    for (n, mv) in &moves[..jump] {
+        stacks.apply(mv, crane).map_err(|e| (*n, e))?;
+    }
+
+    let title = format!("{}: move {}/{}", crane.name(), jump, total);
+    show(&stacks, &title, jump.checked_sub(1).map(|i| &moves[i].1));
+
+    for (i, (n, mv)) in moves.iter().enumerate().skip(jump) {
+        std::thread::sleep(delay);
+        stacks.apply(mv, crane).map_err(|e| (*n, e))?;
+        let title = format!("{}: move {}/{}: {}", crane.name(), i + 1, total, mv);
+        show(&stacks, &title, Some(mv));
+    }
+
+    println!();
+    println!("{}: {}", crane.name(), stacks.tops());
+    return Ok(());
+}
@@ -0,0 +1,78 @@
+use crate::crane::Crane;
+use crate::{Move, Stack, Stacks};
+use std::time::{Duration, Instant};
+
+const NSTACKS: usize = 9;
+const HEIGHT: usize = 100;
+
+// A small linear congruential generator, so that generated inputs are the same on every run.
+struct Lcg(u64);
+
+impl Lcg {
+    fn below(&mut self, limit: usize) -> usize {
+        self.0 = self.0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        return ((self.0 >> 33) as usize) % limit;
+    }
+}
+
+fn generate_stacks() -> Stacks {
+    let labels = (1..=NSTACKS).collect();
+    let stacks = (0..NSTACKS)
+        .map(|i| -> Stack {
+            (0..HEIGHT)
+                .map(|j| ((b'A' + ((i + j) % 26) as u8) as char).to_string())
+                .collect()
+        })
+        .collect();
+    return Stacks{labels, stacks};
+}
+
+// Generate |count| moves which are valid for any crane, since every crane leaves the same number of
+// crates on each stack.
+fn generate_moves(stacks: &Stacks, count: usize, rng: &mut Lcg) -> Vec<Move> {
+    let mut heights: Vec<usize> = stacks.stacks.iter().map(Stack::len).collect();
+    let mut ret = Vec::with_capacity(count);
+
+    while ret.len() < count {
+        let from = rng.below(NSTACKS);
+        let to = rng.below(NSTACKS);
+        if from == to || heights[from] == 0 { continue; }
+
+        let moved = 1 + rng.below(heights[from]);
+        heights[from] -= moved;
+        heights[to] += moved;
+        ret.push(Move{count: moved, from: from + 1, to: to + 1});
+    }
+
+    return ret;
+}
+
+fn time(f: impl FnOnce() -> Stacks) -> (Duration, String) {
+    let start = Instant::now();
+    let tops = f().tops();
+    return (start.elapsed(), tops);
+}
+
+pub fn run(count: usize, cranes: &[Box<dyn Crane>]) {
+    let stacks = generate_stacks();
+    let moves = generate_moves(&stacks, count, &mut Lcg(count as u64));
+
+    println!("{} moves over {} stacks of {} crates", count, NSTACKS, HEIGHT);
+
+    for crane in cranes {
+        let crane = crane.as_ref();
+        let fold = time(|| {
+            moves.iter().fold(stacks.clone(), |acc, mv| acc.perform(mv, crane).unwrap().0)
+        });
+        let apply = time(|| {
+            let mut ret = stacks.clone();
+            for mv in &moves { ret.apply(mv, crane).unwrap(); }
+            ret
+        });
+        assert_eq!(fold.1, apply.1);
+
+        println!("{}: fold {:>12?} in place {:>12?}", crane.name(), fold.0, apply.0);
+    }
+}
@@ -0,0 +1,104 @@
+// A single pick up and put down by a crane: the top |count| crates of the stack at slot |from| are
+// moved as one block, keeping their order, onto the stack at slot |to|.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lift {
+    pub from: usize,
+    pub to: usize,
+    pub count: usize,
+}
+
+pub trait Crane {
+    fn name(&self) -> String;
+
+    // Break a move of |count| crates from slot |from| to slot |to| down into the lifts this crane
+    // needs to carry it out.
+    fn lifts(&self, from: usize, to: usize, count: usize) -> Vec<Lift>;
+}
+
+// Moves crates one at a time, which reverses their order.
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        return "CrateMover 9000".to_owned();
+    }
+
+    fn lifts(&self, from: usize, to: usize, count: usize) -> Vec<Lift> {
+        return vec![Lift{from, to, count: 1}; count];
+    }
+}
+
+// Moves any number of crates at once.
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        return "CrateMover 9001".to_owned();
+    }
+
+    fn lifts(&self, from: usize, to: usize, count: usize) -> Vec<Lift> {
+        return vec![Lift{from, to, count}];
+    }
+}
+
+// Moves at most |capacity| crates at once, taking as many as it can each time.
+pub struct Limited {
+    pub capacity: usize,
+}
+
+impl Crane for Limited {
+    fn name(&self) -> String {
+        return format!("CrateMover limited to {}", self.capacity);
+    }
+
+    fn lifts(&self, from: usize, to: usize, count: usize) -> Vec<Lift> {
+        let mut ret = Vec::new();
+        let mut left = count;
+        while left > 0 {
+            let lifted = std::cmp::min(left, self.capacity);
+            ret.push(Lift{from, to, count: lifted});
+            left -= lifted;
+        }
+        return ret;
+    }
+}
+
+// Moves any number of crates at once, but only onto a neighbouring stack, so longer moves are
+// relayed through every stack in between.
+pub struct Adjacent;
+
+impl Crane for Adjacent {
+    fn name(&self) -> String {
+        return "adjacent-only CrateMover".to_owned();
+    }
+
+    fn lifts(&self, from: usize, to: usize, count: usize) -> Vec<Lift> {
+        let mut ret = Vec::new();
+        let mut cur = from;
+        while cur != to {
+            let next = if cur < to { cur + 1 } else { cur - 1 };
+            ret.push(Lift{from: cur, to: next, count});
+            cur = next;
+        }
+        return ret;
+    }
+}
+
+// Select a crane by name: "9000", "9001", "adjacent", or "limited:K" for a crane which moves at
+// most K crates at once.
+pub fn parse(spec: &str) -> Result<Box<dyn Crane>, String> {
+    if let Some(capacity) = spec.strip_prefix("limited:") {
+        let capacity = capacity.parse::<usize>()
+            .ok()
+            .filter(|&c| c > 0)
+            .ok_or_else(|| format!("bad capacity '{}' for limited crane", capacity))?;
+        return Ok(Box::new(Limited{capacity}));
+    }
+
+    return match spec {
+        "9000" => Ok(Box::new(CrateMover9000)),
+        "9001" => Ok(Box::new(CrateMover9001)),
+        "adjacent" => Ok(Box::new(Adjacent)),
+        _ => Err(format!("unknown crane '{}'", spec)),
+    };
+}
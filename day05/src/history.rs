@@ -0,0 +1,64 @@
+use crate::crane::Crane;
+use crate::{Move, Stacks};
+
+// How many moves apart the saved states are. Any other state is found by replaying moves from the
+// closest saved state before it.
+const INTERVAL: usize = 64;
+
+// Every state the stacks pass through while a list of moves is carried out.
+pub struct History<'a> {
+    crane: &'a dyn Crane,
+    moves: Vec<Move>,
+    // checkpoints[i] is the state after i * INTERVAL moves.
+    checkpoints: Vec<Stacks>,
+}
+
+impl<'a> History<'a> {
+    // Carry out |moves| on |initial| with |crane|, failing with the line number of the first move
+    // that can't be done.
+    pub fn new(
+        initial: &Stacks,
+        moves: &[(usize, Move)],
+        crane: &'a dyn Crane,
+    ) -> Result<Self, (usize, String)> {
+        let mut stacks = initial.clone();
+        let mut checkpoints = vec![stacks.clone()];
+
+        for (i, (n, mv)) in moves.iter().enumerate() {
+            stacks.apply(mv, crane).map_err(|e| (*n, e))?;
+            if (i + 1) % INTERVAL == 0 { checkpoints.push(stacks.clone()); }
+        }
+
+        let moves = moves.iter().map(|(_, mv)| mv.clone()).collect();
+        return Ok(History{crane, moves, checkpoints});
+    }
+
+    pub fn len(&self) -> usize {
+        return self.moves.len();
+    }
+
+    // The stacks after the first |n| moves.
+    pub fn state(&self, n: usize) -> Stacks {
+        assert!(n <= self.len(), "only {} moves in the history", self.len());
+
+        let mut ret = self.checkpoints[n / INTERVAL].clone();
+        for mv in &self.moves[n / INTERVAL * INTERVAL..n] {
+            ret.apply(mv, self.crane).expect("history was already checked");
+        }
+        return ret;
+    }
+}
+
+// Work out what the stacks looked like before |moves| were carried out with |crane|, given what
+// they looked like after.
+pub fn reconstruct(
+    last: &Stacks,
+    moves: &[(usize, Move)],
+    crane: &dyn Crane,
+) -> Result<Stacks, (usize, String)> {
+    let mut ret = last.clone();
+    for (n, mv) in moves.iter().rev() {
+        ret.undo(mv, crane).map_err(|e| (*n, e))?;
+    }
+    return Ok(ret);
+}
@@ -1,4 +1,16 @@
-type Stack = std::collections::VecDeque<char>;
+#![allow(clippy::needless_return)]
+
+mod animate;
+mod bench;
+mod crane;
+mod history;
+mod planner;
+mod validate;
+
+use crane::{Crane, Lift};
+
+type Crate = String;
+type Stack = Vec<Crate>;
 
 #[derive(Debug, Clone)]
 struct Move {
@@ -8,98 +20,444 @@ struct Move {
 }
 
 impl Move {
-    fn new(line: String) -> Self {
-        fn parse(n: &str) -> usize {
-            n.parse::<usize>().unwrap()
+    // Parse a line like "move 3 from 1 to 2".
+    fn parse(line: &str) -> Result<Self, String> {
+        fn number(n: Option<&str>, what: &str) -> Result<usize, String> {
+            let n = n.ok_or_else(|| format!("missing {}", what))?;
+            return n.parse::<usize>()
+                .map_err(|_| format!("expected a number for {}, found '{}'", what, n));
+        }
+        fn keyword(word: Option<&str>, expected: &str) -> Result<(), String> {
+            return match word {
+                Some(w) if w == expected => Ok(()),
+                Some(w) => Err(format!("expected '{}', found '{}'", expected, w)),
+                None => Err(format!("expected '{}'", expected)),
+            };
         }
 
-        let pieces = line.split(" ")
-            .collect::<Vec<&str>>();
-        let count = parse(pieces[1]);
-        let from = parse(pieces[3]) - 1;
-        let to = parse(pieces[5]) - 1;
+        let mut pieces = line.split_whitespace();
+        keyword(pieces.next(), "move")?;
+        let count = number(pieces.next(), "the crate count")?;
+        keyword(pieces.next(), "from")?;
+        let from = number(pieces.next(), "the source stack")?;
+        keyword(pieces.next(), "to")?;
+        let to = number(pieces.next(), "the destination stack")?;
+        if let Some(extra) = pieces.next() {
+            return Err(format!("unexpected '{}' after the move", extra));
+        }
 
-        return Move{count: count, from: from, to: to};
+        return Ok(Move{count, from, to});
     }
 }
 
-#[derive(Debug, Clone)]
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return write!(f, "move {} from {} to {}", self.count, self.from, self.to);
+    }
+}
+
+// A whitespace separated word in a drawing row, along with the (inclusive) character columns it
+// spans.
+struct Token {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+impl Token {
+    fn overlaps(&self, other: &Token) -> bool {
+        return self.start <= other.end && other.start <= self.end;
+    }
+}
+
+fn tokenize(row: &str) -> Vec<Token> {
+    let mut ret = Vec::new();
+    let mut cur: Option<Token> = None;
+
+    for (col, c) in row.chars().enumerate() {
+        if c.is_whitespace() {
+            if let Some(token) = cur.take() { ret.push(token); }
+            continue;
+        }
+        match cur.as_mut() {
+            Some(token) => {
+                token.end = col;
+                token.text.push(c);
+            }
+            None => cur = Some(Token{start: col, end: col, text: c.to_string()}),
+        }
+    }
+    if let Some(token) = cur { ret.push(token); }
+
+    return ret;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Stacks {
+    labels: Vec<usize>,
     stacks: Vec<Stack>,
 }
 
 impl Stacks {
-    fn new(nstacks: usize) -> Self {
-        let stacks = (0..nstacks).into_iter()
-            .map(|_| Stack::new())
-            .collect();
-        return Self{stacks: stacks};
+    // Parse a drawing of the stacks. The last row must be the stack labels; every row above it
+    // holds crates like "[A]", each of which sits above exactly one label. Rows may be shorter
+    // than the label row, and crates may have labels longer than one character.
+    fn parse(rows: &[String]) -> Result<Self, String> {
+        let (label_row, crate_rows) = rows.split_last()
+            .ok_or("drawing is empty")?;
+        let label_line = rows.len();
+
+        let label_tokens = tokenize(label_row);
+        if label_tokens.is_empty() {
+            return Err(format!("line {}: expected a row of stack labels", label_line));
+        }
+
+        let mut labels = Vec::new();
+        for token in &label_tokens {
+            let label = token.text.parse::<usize>()
+                .map_err(|_| format!(
+                    "line {}: stack label '{}' at column {} is not a number",
+                    label_line, token.text, token.start + 1))?;
+            if labels.contains(&label) {
+                return Err(format!("line {}: stack label {} appears twice", label_line, label));
+            }
+            labels.push(label);
+        }
+
+        let mut stacks: Vec<Stack> = labels.iter().map(|_| Stack::new()).collect();
+
+        // Whether a stack can still have crates put on top of it; once a row leaves a gap in a
+        // stack, any crate above that gap would be floating.
+        let mut open = vec![true; labels.len()];
+
+        for (i, row) in crate_rows.iter().enumerate().rev() {
+            let line = i + 1;
+            let mut filled = vec![false; labels.len()];
+
+            for token in tokenize(row) {
+                let name = token.text
+                    .strip_prefix('[')
+                    .and_then(|t| t.strip_suffix(']'))
+                    .filter(|t| !t.is_empty())
+                    .ok_or_else(|| format!(
+                        "line {}: expected a crate like '[A]' at column {}, found '{}'",
+                        line, token.start + 1, token.text))?;
+
+                let mut slots = label_tokens.iter()
+                    .enumerate()
+                    .filter(|(_, label)| label.overlaps(&token))
+                    .map(|(slot, _)| slot);
+                let slot = match (slots.next(), slots.next()) {
+                    (Some(slot), None) => slot,
+                    (None, _) => return Err(format!(
+                        "line {}: crate {} at column {} is not above any stack label",
+                        line, token.text, token.start + 1)),
+                    (Some(_), Some(_)) => return Err(format!(
+                        "line {}: crate {} at column {} spans more than one stack label",
+                        line, token.text, token.start + 1)),
+                };
+
+                if filled[slot] {
+                    return Err(format!(
+                        "line {}: more than one crate above stack {}", line, labels[slot]));
+                }
+                if !open[slot] {
+                    return Err(format!(
+                        "line {}: crate {} in stack {} is floating above an empty space",
+                        line, token.text, labels[slot]));
+                }
+                filled[slot] = true;
+                stacks[slot].push(name.to_owned());
+            }
+
+            for (slot, was_filled) in filled.into_iter().enumerate() {
+                if !was_filled { open[slot] = false; }
+            }
+        }
+
+        return Ok(Self{labels, stacks});
+    }
+
+    // Map a stack label as written in the drawing and the moves to its index.
+    fn slot(&self, label: usize) -> Result<usize, String> {
+        return self.labels.iter()
+            .position(|&l| l == label)
+            .ok_or_else(|| format!("there is no stack {}", label));
     }
 
-    fn parse_initial(&mut self, rows: Vec<String>) {
-        fn cell_index(cell: usize) -> usize {
-            return cell * 4 + 1;
+    // Make sure |mv| names real stacks and has enough crates to move.
+    fn check(&self, mv: &Move) -> Result<(), String> {
+        let from = self.slot(mv.from)?;
+        self.slot(mv.to)?;
+
+        let height = self.stacks[from].len();
+        if height < mv.count {
+            return Err(format!(
+                "stack {} has {} crates but {} need to be moved", mv.from, height, mv.count));
         }
-        let mut parse_row = |row: &String| {
-            for i in 0..self.stacks.len() {
-                let index = cell_index(i);
-                let box_at = row.chars().nth(index).unwrap();
-                if box_at == ' ' { continue; }
-                self.stacks[i].push_front(box_at);
+        return Ok(());
+    }
+
+    // Take the top |count| crates off the stack at |slot|, bottom-most first.
+    fn take(&mut self, slot: usize, count: usize) -> Stack {
+        let stack = &mut self.stacks[slot];
+        let at = stack.len()
+            .checked_sub(count)
+            .expect("Move from empty");
+        return stack.split_off(at);
+    }
+
+    fn lift(&mut self, lift: &Lift) {
+        let crates = self.take(lift.from, lift.count);
+        self.stacks[lift.to].extend(crates);
+    }
+
+    // The lifts |crane| needs to carry out |mv| on these stacks.
+    fn lifts(&self, mv: &Move, crane: &dyn Crane) -> Result<Vec<Lift>, String> {
+        self.check(mv)?;
+        return Ok(crane.lifts(self.slot(mv.from)?, self.slot(mv.to)?, mv.count));
+    }
+
+    // Carry out |mv| in place with |crane|, returning the number of lifts it took. Nothing is
+    // moved if |mv| is invalid.
+    fn apply(&mut self, mv: &Move, crane: &dyn Crane) -> Result<usize, String> {
+        let lifts = self.lifts(mv, crane)?;
+        for lift in &lifts { self.lift(lift); }
+        return Ok(lifts.len());
+    }
+
+    // Reverse |mv| in place, as if it had been carried out by |crane|. Nothing is moved if the
+    // stacks couldn't have been left this way by |mv|.
+    fn undo(&mut self, mv: &Move, crane: &dyn Crane) -> Result<usize, String> {
+        let lifts: Vec<Lift> = crane.lifts(self.slot(mv.from)?, self.slot(mv.to)?, mv.count)
+            .into_iter()
+            .rev()
+            .map(|lift| Lift{from: lift.to, to: lift.from, count: lift.count})
+            .collect();
+
+        let mut heights: Vec<usize> = self.stacks.iter().map(Stack::len).collect();
+        for lift in &lifts {
+            if heights[lift.from] < lift.count {
+                return Err(format!(
+                    "stack {} has {} crates but {} need to be put back",
+                    self.labels[lift.from], heights[lift.from], lift.count));
             }
-        };
-        for row in rows {
-            if !row.contains("[") { break; }
-            parse_row(&row);
+            heights[lift.from] -= lift.count;
+            heights[lift.to] += lift.count;
         }
+
+        for lift in &lifts { self.lift(lift); }
+        return Ok(lifts.len());
     }
-    
-    fn perform_part1(&self, mv: &Move) -> Self {
+
+    // Like |apply|, but leaves these stacks alone and returns new ones along with the lift count.
+    fn perform(&self, mv: &Move, crane: &dyn Crane) -> Result<(Self, usize), String> {
         let mut ret = self.clone();
+        let lifts = ret.apply(mv, crane)?;
+        return Ok((ret, lifts));
+    }
+
+    // A one line description of every stack, bottom crate first.
+    fn summary(&self) -> String {
+        return self.labels.iter()
+            .zip(&self.stacks)
+            .map(|(label, stack)| {
+                let crates: String = stack.iter().map(|c| format!("[{}]", c)).collect();
+                format!("{}:{}", label, crates)
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
 
-        for _ in 0..mv.count {
-            let cur = ret
-                .stacks[mv.from]
-                .pop_back()
-                .expect("Move from empty");
-            ret.stacks[mv.to].push_back(cur);
+    // Draw the stacks in the same format that |parse| accepts. Every column is wide enough for its
+    // label and its widest crate (or a one letter crate if it is empty), and |highlight| decides
+    // which crates (by stack index and height from the bottom) are shown in reverse video.
+    fn draw(&self, highlight: impl Fn(usize, usize) -> bool) -> Vec<String> {
+        fn pad(text: &str, width: usize) -> (String, String) {
+            let extra = width - text.chars().count();
+            return (" ".repeat(extra / 2), " ".repeat(extra - extra / 2));
         }
 
+        let labels: Vec<String> = self.labels.iter().map(usize::to_string).collect();
+        let widths: Vec<usize> = labels.iter()
+            .zip(&self.stacks)
+            .map(|(label, stack)| {
+                let widest = stack.iter().map(|c| c.chars().count() + 2).fold(3, std::cmp::max);
+                std::cmp::max(label.chars().count(), widest)
+            })
+            .collect();
+        let height = self.stacks.iter().map(Stack::len).max().unwrap_or(0);
+
+        let mut ret = Vec::new();
+        for level in (0..height).rev() {
+            let cells: Vec<String> = self.stacks.iter()
+                .enumerate()
+                .map(|(slot, stack)| {
+                    let Some(name) = stack.get(level) else { return " ".repeat(widths[slot]); };
+                    let text = format!("[{}]", name);
+                    let (left, right) = pad(&text, widths[slot]);
+                    if highlight(slot, level) {
+                        format!("{}\x1b[7m{}\x1b[0m{}", left, text, right)
+                    } else {
+                        format!("{}{}{}", left, text, right)
+                    }
+                })
+                .collect();
+            ret.push(cells.join(" "));
+        }
+
+        let cells: Vec<String> = labels.iter()
+            .zip(&widths)
+            .map(|(label, &width)| {
+                let (left, right) = pad(label, width);
+                format!("{}{}{}", left, label, right)
+            })
+            .collect();
+        ret.push(cells.join(" "));
+
         return ret;
     }
 
-    fn perform_part2(&self, mv: &Move) -> Self {
-        let mut ret = self.clone();
-        let mut boxes = Vec::new();
-        for _ in 0..mv.count {
-            let cur = ret.
-                stacks[mv.from]
-                .pop_back()
-                .expect("move from empty");
-            boxes.push(cur);
-        }
-        boxes.reverse();
-        for i in boxes { ret.stacks[mv.to].push_back(i); }
-        return ret;
+    fn render(&self) -> String {
+        return self.draw(|_, _| false).join("\n");
     }
 
     fn tops(&self) -> String {
         let mut ret = String::new();
 
         for stack in &self.stacks {
-            if stack.is_empty() { continue; }
-            ret.push(*stack.back().unwrap());
+            if let Some(top) = stack.last() { ret.push_str(top); }
+        }
+        return ret;
+    }
+}
+
+struct Options {
+    // Use the functional API, which copies the stacks for every move.
+    fold: bool,
+    // Instead of solving the input, time both APIs on this many generated moves.
+    bench: Option<usize>,
+    // Report every problem with the moves instead of solving the input.
+    check: bool,
+    // Print the final stacks as a drawing.
+    draw: bool,
+    // Redraw the stacks after every move, starting after move |jump|.
+    animate: bool,
+    delay: std::time::Duration,
+    jump: usize,
+    // Treat the drawing as the stacks after the moves, and work out what they were before.
+    reverse: bool,
+    // Print the stacks after this many moves.
+    state: Option<usize>,
+    // Instead of carrying out the moves, find the fewest moves that reach this goal.
+    goal: Option<planner::Goal>,
+    max_states: usize,
+    cranes: Vec<Box<dyn Crane>>,
+}
+
+impl Options {
+    fn parse(args: impl Iterator<Item = String>) -> Self {
+        let mut ret = Options{
+            fold: false,
+            bench: None,
+            check: false,
+            draw: false,
+            animate: false,
+            delay: std::time::Duration::from_millis(500),
+            jump: 0,
+            reverse: false,
+            state: None,
+            goal: None,
+            max_states: 1000000,
+            cranes: Vec::new(),
+        };
+        let mut args = args.skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--fold" => ret.fold = true,
+                "--check" => ret.check = true,
+                "--draw" => ret.draw = true,
+                "--animate" => ret.animate = true,
+                "--reverse" => ret.reverse = true,
+                "--state" => {
+                    let n = args.next()
+                        .expect("--state needs a move number")
+                        .parse::<usize>()
+                        .expect("move number should parse");
+                    ret.state = Some(n);
+                }
+                "--delay" => {
+                    let millis = args.next()
+                        .expect("--delay needs a number of milliseconds")
+                        .parse::<u64>()
+                        .expect("delay should parse");
+                    ret.delay = std::time::Duration::from_millis(millis);
+                }
+                "--jump" => {
+                    ret.jump = args.next()
+                        .expect("--jump needs a move number")
+                        .parse::<usize>()
+                        .expect("move number should parse");
+                }
+                "--bench" => {
+                    let moves = args.next()
+                        .expect("--bench needs a move count")
+                        .parse::<usize>()
+                        .expect("move count should parse");
+                    ret.bench = Some(moves);
+                }
+                "--plan" => {
+                    let path = args.next().expect("--plan needs a drawing file");
+                    let text = std::fs::read_to_string(&path)
+                        .unwrap_or_else(|e| panic!("can't read {}: {}", path, e));
+                    let rows = text.lines()
+                        .take_while(|i| !i.is_empty())
+                        .map(str::to_owned)
+                        .collect::<Vec<String>>();
+                    let target = Stacks::parse(&rows)
+                        .unwrap_or_else(|e| panic!("invalid drawing in {}: {}", path, e));
+                    ret.goal = Some(planner::Goal::Drawing(target));
+                }
+                "--plan-tops" => {
+                    let tops = args.next().expect("--plan-tops needs the top crates");
+                    ret.goal = Some(planner::Goal::Tops(tops));
+                }
+                "--max-states" => {
+                    ret.max_states = args.next()
+                        .expect("--max-states needs a number")
+                        .parse::<usize>()
+                        .expect("state limit should parse");
+                }
+                "--crane" => {
+                    let spec = args.next().expect("--crane needs a crane");
+                    let crane = crane::parse(&spec).unwrap_or_else(|e| panic!("{}", e));
+                    ret.cranes.push(crane);
+                }
+                _ => panic!("Unknown argument {}", arg),
+            }
         }
+
+        if ret.cranes.is_empty() {
+            ret.cranes.push(Box::new(crane::CrateMover9000));
+            ret.cranes.push(Box::new(crane::CrateMover9001));
+        }
+
         return ret;
     }
 }
 
+fn fail(message: String) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
 fn main() {
-    let stacks = std::env::args()
-        .nth(1)
-        .expect("Expected one argument")
-        .parse::<usize>()
-        .expect("Argument should parse");
+    let options = Options::parse(std::env::args());
+    if let Some(moves) = options.bench {
+        bench::run(moves, &options.cranes);
+        return;
+    }
 
     let mut lines = std::io::stdin()
         .lines()
@@ -108,14 +466,97 @@ fn main() {
     let crates = (&mut lines)
         .take_while(|i| !i.is_empty())
         .collect::<Vec<String>>();
-    let moves = lines.into_iter().map(Move::new).collect::<Vec<Move>>();
 
-    let mut towers = Stacks::new(stacks);
-    towers.parse_initial(crates);
+    // Keep the line number of every move for error messages. The drawing and the blank line after
+    // it come first.
+    let first_move_line = crates.len() + 2;
+    let move_lines = lines.enumerate()
+        .map(|(i, line)| (i + first_move_line, line))
+        .filter(|(_, line)| !line.trim().is_empty())
+        .collect::<Vec<(usize, String)>>();
+
+    let towers = Stacks::parse(&crates)
+        .unwrap_or_else(|e| fail(format!("invalid drawing: {}", e)));
 
-    let part1 = moves.iter().fold(towers.clone(), |acc, i| acc.perform_part1(&i));
-    let part2 = moves.into_iter().fold(towers, |acc, i| acc.perform_part2(&i));
+    // The plan is printed as a complete input, so it can be fed straight back in. Only the first
+    // crane is used, since the plans for different cranes would be mixed together otherwise.
+    if let Some(goal) = &options.goal {
+        let crane = options.cranes[0].as_ref();
+        let plan = planner::plan(&towers, goal, crane, options.max_states)
+            .unwrap_or_else(|e| fail(format!("{}: {}", crane.name(), e)));
+        eprintln!("{}: {} moves, looked at {} arrangements",
+                  crane.name(), plan.moves.len(), plan.explored);
+        println!("{}", towers.render());
+        println!();
+        for mv in plan.moves { println!("{}", mv); }
+        return;
+    }
 
-    println!("part 1: {}", part1.tops());
-    println!("part 2: {}", part2.tops());
+    if options.check {
+        let mut ok = true;
+        for crane in &options.cranes {
+            let problems = validate::validate(&towers, &move_lines, crane.as_ref());
+            ok &= problems.is_empty();
+            validate::report(crane.as_ref(), &problems);
+        }
+        if !ok { std::process::exit(1); }
+        return;
+    }
+
+    let moves = move_lines.iter()
+        .map(|(n, line)| {
+            let mv = Move::parse(line)
+                .unwrap_or_else(|e| fail(format!("line {}: {}", n, e)));
+            (*n, mv)
+        })
+        .collect::<Vec<(usize, Move)>>();
+
+    if options.reverse || options.state.is_some() {
+        for crane in &options.cranes {
+            let crane = crane.as_ref();
+            let initial = match options.reverse {
+                true => history::reconstruct(&towers, &moves, crane),
+                false => Ok(towers.clone()),
+            };
+            let history = initial
+                .and_then(|initial| history::History::new(&initial, &moves, crane))
+                .unwrap_or_else(|(n, e)| fail(format!("{}: line {}: {}", crane.name(), n, e)));
+
+            let n = options.state.unwrap_or(0);
+            if n > history.len() {
+                fail(format!("there are only {} moves", history.len()));
+            }
+            println!("{}: after {} of {} moves", crane.name(), n, history.len());
+            println!("{}", history.state(n).render());
+        }
+        return;
+    }
+
+    if options.animate {
+        for crane in &options.cranes {
+            animate::run(&towers, &moves, crane.as_ref(), options.delay, options.jump)
+                .unwrap_or_else(|(n, e)| fail(format!("line {}: {}", n, e)));
+        }
+        return;
+    }
+
+    for crane in &options.cranes {
+        let crane = crane.as_ref();
+        let result = if options.fold {
+            moves.iter().try_fold((towers.clone(), 0), |(acc, lifts), (n, mv)| {
+                let (next, more) = acc.perform(mv, crane).map_err(|e| (n, e))?;
+                Ok((next, lifts + more))
+            })
+        } else {
+            let mut result = towers.clone();
+            moves.iter()
+                .map(|(n, mv)| result.apply(mv, crane).map_err(|e| (n, e)))
+                .sum::<Result<usize, _>>()
+                .map(|lifts| (result, lifts))
+        };
+        let (result, lifts) = result
+            .unwrap_or_else(|(n, e)| fail(format!("line {}: {}", n, e)));
+        println!("{}: {} ({} lifts)", crane.name(), result.tops(), lifts);
+        if options.draw { println!("{}", result.render()); }
+    }
 }
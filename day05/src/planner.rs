@@ -0,0 +1,102 @@
+use crate::crane::Crane;
+use crate::{Move, Stacks};
+use std::collections::HashSet;
+
+pub enum Goal {
+    // Every stack must look exactly like this.
+    Drawing(Stacks),
+    // Only the crates on top of the stacks matter, as reported by |Stacks::tops|.
+    Tops(String),
+}
+
+impl Goal {
+    fn reached(&self, stacks: &Stacks) -> bool {
+        return match self {
+            Goal::Drawing(target) => stacks == target,
+            Goal::Tops(tops) => &stacks.tops() == tops,
+        };
+    }
+
+    // Catch goals which can never be reached before searching for them.
+    fn check(&self, initial: &Stacks) -> Result<(), String> {
+        let Goal::Drawing(target) = self else { return Ok(()); };
+        if target.labels != initial.labels {
+            return Err("the target drawing has different stacks".to_owned());
+        }
+
+        let crates = |stacks: &Stacks| -> Vec<String> {
+            let mut ret: Vec<String> = stacks.stacks.iter().flatten().cloned().collect();
+            ret.sort();
+            return ret;
+        };
+        if crates(target) != crates(initial) {
+            return Err("the target drawing has different crates".to_owned());
+        }
+        return Ok(());
+    }
+}
+
+pub struct Plan {
+    pub moves: Vec<Move>,
+    // How many different arrangements were looked at to find the plan.
+    pub explored: usize,
+}
+
+// Every move that can be made from |stacks|.
+fn possible_moves(stacks: &Stacks) -> Vec<Move> {
+    let mut ret = Vec::new();
+    for (from, stack) in stacks.labels.iter().zip(&stacks.stacks) {
+        for to in &stacks.labels {
+            if from == to { continue; }
+            for count in 1..=stack.len() {
+                ret.push(Move{count, from: *from, to: *to});
+            }
+        }
+    }
+    return ret;
+}
+
+// Breadth first search for the fewest moves which get |crane| from |initial| to |goal|, giving up
+// after looking at |limit| arrangements.
+pub fn plan(
+    initial: &Stacks,
+    goal: &Goal,
+    crane: &dyn Crane,
+    limit: usize,
+) -> Result<Plan, String> {
+    goal.check(initial)?;
+
+    // Arrangements in the order they were found, each with the arrangement and move it came from.
+    let mut found: Vec<(Stacks, Option<(usize, Move)>)> = vec![(initial.clone(), None)];
+    let mut seen = HashSet::from([initial.clone()]);
+
+    let mut next = 0;
+    while next < found.len() {
+        if goal.reached(&found[next].0) { break; }
+
+        for mv in possible_moves(&found[next].0) {
+            let (stacks, _) = found[next].0.perform(&mv, crane)?;
+            if seen.contains(&stacks) { continue; }
+            if seen.len() >= limit {
+                return Err(format!("gave up after looking at {} arrangements", limit));
+            }
+            seen.insert(stacks.clone());
+            found.push((stacks, Some((next, mv))));
+        }
+        next += 1;
+    }
+
+    if next == found.len() {
+        return Err(format!("the goal can't be reached with the {}", crane.name()));
+    }
+
+    let mut moves = Vec::new();
+    let mut cur = next;
+    while let Some((parent, mv)) = &found[cur].1 {
+        moves.push(mv.clone());
+        cur = *parent;
+    }
+    moves.reverse();
+
+    return Ok(Plan{moves, explored: found.len()});
+}
@@ -0,0 +1,40 @@
+use crate::crane::Crane;
+use crate::{Move, Stacks};
+
+// Something wrong with one line of the move list.
+pub struct Problem {
+    pub line: usize,
+    pub message: String,
+    // The stacks just before the bad move.
+    pub state: String,
+}
+
+// Simulate |lines| (each paired with its line number) with |crane| and report every move which is
+// malformed or can't be carried out. Bad moves are skipped, so the stacks reported for later
+// problems are what they would be if the bad moves were deleted from the list.
+pub fn validate(stacks: &Stacks, lines: &[(usize, String)], crane: &dyn Crane) -> Vec<Problem> {
+    let mut stacks = stacks.clone();
+    let mut ret = Vec::new();
+
+    for (line, text) in lines {
+        let result = Move::parse(text).and_then(|mv| stacks.apply(&mv, crane));
+        if let Err(message) = result {
+            ret.push(Problem{line: *line, message, state: stacks.summary()});
+        }
+    }
+
+    return ret;
+}
+
+pub fn report(crane: &dyn Crane, problems: &[Problem]) {
+    if problems.is_empty() {
+        println!("{}: all moves are valid", crane.name());
+        return;
+    }
+
+    println!("{}: {} invalid moves", crane.name(), problems.len());
+    for problem in problems {
+        println!("  line {}: {}", problem.line, problem.message);
+        println!("    stacks: {}", problem.state);
+    }
+}
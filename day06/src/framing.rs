@@ -0,0 +1,91 @@
+use crate::Scanner;
+use crate::symbol::Symbol;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Packet,
+    Message,
+}
+
+// A piece of a datastream introduced by a marker.
+#[derive(Debug)]
+pub struct Frame<'a, S> {
+    pub kind: Kind,
+    // Where the marker starts.
+    pub marker: usize,
+    // Where the payload starts, right after the marker.
+    pub offset: usize,
+    // Everything up to the start of the next marker, or the end of the stream.
+    pub payload: &'a [S],
+}
+
+#[derive(Debug)]
+pub struct Decoded<'a, S> {
+    // Symbols before the first marker, which don't belong to any frame.
+    pub skipped: usize,
+    pub frames: Vec<Frame<'a, S>>,
+}
+
+// The end of the first window of |length| different symbols in |data| at or after |from|.
+fn first_marker<S: Symbol>(data: &[S], from: usize, length: usize) -> Option<usize> {
+    let input = data[from..].iter().copied();
+    return Scanner::new(input, &[length], true)
+        .next()
+        .map(|(_, end)| from + end);
+}
+
+// Split |data| into frames, each starting with a start of packet marker (|packet_len| different
+// symbols) or a start of message marker (|message_len| different symbols). Markers never overlap,
+// so the search for the next one starts after the end of the last. Every message marker begins
+// with a shorter run that looks like a packet marker, so a marker is only a packet marker if no
+// message marker starts at the same place.
+//
+// Fails if there is no marker at all, since then nothing in the stream can be trusted.
+pub fn decode<S: Symbol>(
+    data: &[S],
+    packet_len: usize,
+    message_len: usize,
+) -> Result<Decoded<'_, S>, String> {
+    assert!(packet_len < message_len, "packet markers must be shorter than message markers");
+
+    // (kind, marker start, payload start) for every marker, in order.
+    let mut markers = Vec::new();
+    let mut pos = 0;
+
+    // The next message marker is often far away, so remember it rather than searching for it again
+    // after every packet marker.
+    let mut next_message = first_marker(data, pos, message_len);
+
+    while let Some(packet_end) = first_marker(data, pos, packet_len) {
+        let packet_start = packet_end - packet_len;
+
+        if next_message.is_some_and(|end| end - message_len < pos) {
+            next_message = first_marker(data, pos, message_len);
+        }
+
+        let marker = match next_message {
+            Some(end) if end - message_len <= packet_start => {
+                (Kind::Message, end - message_len, end)
+            }
+            _ => (Kind::Packet, packet_start, packet_end),
+        };
+        pos = marker.2;
+        markers.push(marker);
+    }
+
+    let Some(&(_, skipped, _)) = markers.first() else {
+        return Err(format!(
+            "no start of packet marker ({} different symbols) in {} symbols",
+            packet_len, data.len()));
+    };
+
+    let frames = markers.iter()
+        .enumerate()
+        .map(|(i, &(kind, marker, offset))| {
+            let end = markers.get(i + 1).map_or(data.len(), |next| next.1);
+            Frame{kind, marker, offset, payload: &data[offset..end]}
+        })
+        .collect();
+
+    return Ok(Decoded{skipped, frames});
+}
@@ -1,65 +1,214 @@
-use std::collections::HashMap;
+#![allow(clippy::needless_return)]
 
-#[derive(Debug)]
-struct Scanner<'a> {
-    line: &'a String,
-    length: usize,
+mod framing;
+mod symbol;
+
+use std::io::Read;
+use symbol::{Symbol, Utf8Chars};
+
+// Looks for points in a datastream where the last few symbols were all different, for several
+// window lengths at once. Symbols are read one at a time, so the stream never has to be held in
+// memory.
+//
+// Yields (length, position) for every such window, where |position| is how many symbols had been
+// read when the window was complete. Windows ending at the same position come out shortest first.
+struct Scanner<S: Symbol, I: Iterator<Item = S>> {
+    input: I,
+    // The window lengths to look for, sorted and without duplicates. None of them is 0.
+    lengths: Vec<usize>,
+    // Only report the first window of each length, and stop reading once they've all been found.
+    first_only: bool,
+    found: Vec<bool>,
+
+    // Where each symbol was last seen, as a count of symbols read before it.
+    last_seen: S::Seen,
+    // Where the longest run of different symbols ending at the current symbol starts.
+    start: usize,
+    read: usize,
+
+    // Lengths[next..ready] still need to be reported for the current position.
+    next: usize,
+    ready: usize,
 }
 
-impl <'a> Scanner<'a> {
-     fn new(length: usize, line: &'a String) -> Self {
-        return Scanner{length: length, line: line};
+impl<S: Symbol, I: Iterator<Item = S>> Scanner<S, I> {
+    fn new(input: I, lengths: &[usize], first_only: bool) -> Self {
+        let mut lengths = lengths.to_vec();
+        lengths.sort();
+        lengths.dedup();
+
+        let found = vec![false; lengths.len()];
+        return Scanner{
+            input,
+            lengths,
+            first_only,
+            found,
+            last_seen: S::Seen::default(),
+            start: 0,
+            read: 0,
+            next: 0,
+            ready: 0,
+        };
     }
 
-    fn scan(&self) -> Option<usize> {
-        return self._scan(0, HashMap::new());
+    // Read the next symbol and work out which window lengths end with it.
+    fn advance(&mut self) -> bool {
+        let Some(symbol) = self.input.next() else { return false; };
+
+        // The run of different symbols can't reach back past the last copy of this one.
+        if let Some(last) = symbol.see(&mut self.last_seen, self.read) {
+            self.start = std::cmp::max(self.start, last + 1);
+        }
+        self.read += 1;
+
+        let run = self.read - self.start;
+        self.next = 0;
+        self.ready = self.lengths.partition_point(|&len| len <= run);
+        return true;
     }
+}
 
-    fn _scan(&self, i: usize, seen: HashMap<char, usize>) -> Option<usize> {
-        if seen.len() == self.length { return Some(i); }
+impl<S: Symbol, I: Iterator<Item = S>> Iterator for Scanner<S, I> {
+    type Item = (usize, usize);
 
-        // If we reach the end and haven't found anything, give up.
-        if i >= self.line.len() { return None; }
-        let cur = self.line.as_bytes()[i] as char;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.next < self.ready {
+                let i = self.next;
+                self.next += 1;
+                if self.first_only && self.found[i] { continue; }
+                self.found[i] = true;
+                return Some((self.lengths[i], self.read));
+            }
 
-        // Add the current letter to the list.
-        let mut new_seen = seen;
-        new_seen.insert(cur, new_seen.get(&cur).unwrap_or(&0) + 1);
+            if self.first_only && self.found.iter().all(|&f| f) { return None; }
+            if !self.advance() { return None; }
+        }
+    }
+}
 
-        // If we haven't read |length| letters yet, scan on.
-        if i < self.length { return self._scan(i + 1, new_seen); }
+struct Options {
+    lengths: Vec<usize>,
+    // Report every marker rather than just the first of each length.
+    all: bool,
+    // Split the streams into packets and messages.
+    frames: bool,
+    // Treat the whole input as one stream of bytes, rather than each line as a stream of chars.
+    binary: bool,
+}
 
-        // Drop the last letter and scan on...
-        let last = self.line.as_bytes()[i - self.length] as char;
-        let last_count = new_seen.remove(&last).expect("Last wasn't present");
-        if last_count > 1 { new_seen.insert(last, last_count - 1); }
+impl Options {
+    fn parse(args: impl Iterator<Item = String>) -> Self {
+        let mut ret = Options{lengths: Vec::new(), all: false, frames: false, binary: false};
+        for arg in args.skip(1) {
+            match arg.as_str() {
+                "--all" => ret.all = true,
+                "--frames" => ret.frames = true,
+                "--binary" => ret.binary = true,
+                _ => {
+                    let length = arg.parse::<usize>()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .expect("expected a positive marker length");
+                    ret.lengths.push(length);
+                }
+            }
+        }
 
-        // Recurse.
-        return self._scan(i + 1, new_seen);
+        // Start of packet and start of message markers.
+        if ret.lengths.is_empty() { ret.lengths = vec![4, 14]; }
+        if ret.frames && ret.lengths.len() != 2 {
+            panic!("framing needs a packet marker length and a message marker length");
+        }
+        if ret.frames && ret.lengths[0] >= ret.lengths[1] {
+            panic!("packet markers must be shorter than message markers");
+        }
+        return ret;
     }
 }
 
-fn make_iter(len: usize, lines: &Vec<String>) -> impl Iterator<Item = usize> + '_ {
-    return lines.iter()
-        .map(move |i| Scanner::new(len, i))
-        .map(move |i| i.scan().expect("did not find"));
+fn print_frames<S: Symbol>(stream: usize, data: &[S], packet_len: usize, message_len: usize) {
+    let decoded = match framing::decode(data, packet_len, message_len) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            println!("stream {}: corrupt: {}", stream, e);
+            return;
+        }
+    };
+
+    println!("stream {}: skipped {} symbols before the first marker", stream, decoded.skipped);
+    for frame in decoded.frames {
+        let kind = match frame.kind {
+            framing::Kind::Packet => "packet",
+            framing::Kind::Message => "message",
+        };
+        println!("  {:7} marker at {:4} payload at {:4} ({} symbols): {}",
+                 kind, frame.marker, frame.offset, frame.payload.len(), S::text(frame.payload));
+    }
 }
 
-fn main() {
-    let sop_len = 4usize;
-    let som_len = 14usize;
+fn print_markers(stream: usize, events: Vec<(usize, usize)>, options: &Options) {
+    if options.all {
+        for (length, position) in events {
+            println!("stream {} length {:2} at {}", stream, length, position);
+        }
+        return;
+    }
+
+    let mut msg = String::new();
+    for length in &options.lengths {
+        let found = events.iter().find(|(len, _)| len == length);
+        msg += &match found {
+            Some((_, position)) => format!("[length {:2} at {:4}] ", length, position),
+            None => format!("[length {:2} not found] ", length),
+        };
+    }
+    println!("{}stream {}", msg, stream);
+}
+
+// Look for markers in every stream in |input|. Streams are separated by |separator|, or if there
+// isn't one the whole input is a single stream.
+fn run<S: Symbol>(input: impl Iterator<Item = S>, separator: Option<S>, options: &Options) {
+    let mut input = input.peekable();
 
-    let lines = std::io::stdin()
-        .lines()
-        .map(|i| i.unwrap().to_owned())
-        .collect::<Vec<String>>();
+    let mut stream = 1;
+    while input.peek().is_some() {
+        let mut data = (&mut input).take_while(|&s| Some(s) != separator);
+        if options.frames {
+            let data = data.collect::<Vec<S>>();
+            print_frames(stream, &data, options.lengths[0], options.lengths[1]);
+        } else {
+            let events = Scanner::new(&mut data, &options.lengths, !options.all).collect();
+            // The scanner may stop early, so skip whatever is left of the stream.
+            data.for_each(drop);
+            print_markers(stream, events, options);
+        }
+        stream += 1;
+    }
+}
+
+// Drop any '\r' that comes right before a '\n', the way `BufRead::lines` does, so that streams
+// with Windows line endings have the same markers as ones without.
+fn strip_cr(input: impl Iterator<Item = char>) -> impl Iterator<Item = char> {
+    let mut input = input.peekable();
+    return std::iter::from_fn(move || {
+        let c = input.next()?;
+        if c == '\r' && input.peek() == Some(&'\n') { return input.next(); }
+        return Some(c);
+    });
+}
+
+fn main() {
+    let options = Options::parse(std::env::args());
 
-    let sops = make_iter(sop_len, &lines);
-    let soms = make_iter(som_len, &lines);
-    let lens = sops.zip(soms);
+    let bytes = std::io::stdin()
+        .lock()
+        .bytes()
+        .map(Result::unwrap);
 
-    for (len_pair, line) in lens.zip(lines.iter()) {
-        let (sop, som) = len_pair;
-        println!("[start of packet {:2}] [start of message {:2}] text {}", sop, som, line);
+    if options.binary {
+        run(bytes, None, &options);
+    } else {
+        run(strip_cr(Utf8Chars::new(bytes)), Some('\n'), &options);
     }
 }
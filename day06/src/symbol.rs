@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+// Something a datastream is made of. Each kind of symbol keeps track of where it was last seen in
+// its own way, since a table for every possible char would be far too big.
+pub trait Symbol: Copy + PartialEq {
+    type Seen: Default;
+
+    // Remember that this symbol was seen at |pos|, returning where it was seen before that.
+    fn see(self, seen: &mut Self::Seen, pos: usize) -> Option<usize>;
+
+    // Show a run of symbols to a person.
+    fn text(symbols: &[Self]) -> String;
+}
+
+pub struct ByteTable([Option<usize>; 256]);
+
+impl Default for ByteTable {
+    fn default() -> Self {
+        return ByteTable([None; 256]);
+    }
+}
+
+// Raw bytes, for binary streams.
+impl Symbol for u8 {
+    type Seen = ByteTable;
+
+    fn see(self, seen: &mut ByteTable, pos: usize) -> Option<usize> {
+        return seen.0[self as usize].replace(pos);
+    }
+
+    fn text(symbols: &[Self]) -> String {
+        return symbols.escape_ascii().to_string();
+    }
+}
+
+// Most text is ASCII, so those chars get a table of their own rather than going through the map.
+#[derive(Default)]
+pub struct CharTable {
+    ascii: ByteTable,
+    other: HashMap<char, usize>,
+}
+
+// Unicode scalar values, for text streams.
+impl Symbol for char {
+    type Seen = CharTable;
+
+    fn see(self, seen: &mut CharTable, pos: usize) -> Option<usize> {
+        if self.is_ascii() { return (self as u8).see(&mut seen.ascii, pos); }
+        return seen.other.insert(self, pos);
+    }
+
+    fn text(symbols: &[Self]) -> String {
+        return symbols.iter().collect();
+    }
+}
+
+// Decodes UTF-8 a byte at a time, so text can be streamed without reading whole lines. Anything
+// that isn't valid UTF-8 comes out as U+FFFD.
+pub struct Utf8Chars<I: Iterator<Item = u8>> {
+    input: std::iter::Peekable<I>,
+}
+
+impl<I: Iterator<Item = u8>> Utf8Chars<I> {
+    pub fn new(input: I) -> Self {
+        return Utf8Chars{input: input.peekable()};
+    }
+}
+
+impl<I: Iterator<Item = u8>> Iterator for Utf8Chars<I> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let first = self.input.next()?;
+        let len = match first.leading_ones() {
+            0 => return Some(first as char),
+            2 => 2,
+            3 => 3,
+            4 => 4,
+            _ => return Some(char::REPLACEMENT_CHARACTER),
+        };
+
+        // Only take continuation bytes, so a truncated sequence doesn't eat the next character.
+        let mut buf = vec![first];
+        while buf.len() < len {
+            match self.input.next_if(|b| b.leading_ones() == 1) {
+                Some(b) => buf.push(b),
+                None => return Some(char::REPLACEMENT_CHARACTER),
+            }
+        }
+
+        return Some(std::str::from_utf8(&buf)
+            .ok()
+            .and_then(|s| s.chars().next())
+            .unwrap_or(char::REPLACEMENT_CHARACTER));
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+// Somewhere a transcript contradicts itself or leaves something out.
+pub struct Issue {
+    pub line: usize,
+    pub message: String,
+}
+
+// What a transcript has said so far about each path, so contradictions can be spotted as they
+// happen and gaps found once it's over. Paths are full paths like "/a/e".
+#[derive(Default)]
+pub struct Log {
+    issues: Vec<Issue>,
+    // The first line each directory was listed by `ls` in its parent, and the first line it was
+    // entered with `cd`.
+    listed: HashMap<String, usize>,
+    visited: HashMap<String, usize>,
+    // The line each file was last listed on, along with the size it was listed with.
+    files: HashMap<String, (usize, usize)>,
+}
+
+impl Log {
+    pub fn conflict(&mut self, line: usize, message: String) {
+        self.issues.push(Issue{line, message});
+    }
+
+    pub fn list_dir(&mut self, path: String, line: usize) {
+        self.listed.entry(path).or_insert(line);
+    }
+
+    pub fn visit(&mut self, path: String, line: usize) {
+        self.visited.entry(path).or_insert(line);
+    }
+
+    pub fn list_file(&mut self, path: String, size: usize, line: usize) {
+        if let Some((first, old_size)) = self.files.insert(path.clone(), (line, size)) {
+            if old_size != size {
+                self.conflict(line, format!(
+                    "{} is listed with size {}, but it had size {} on line {}",
+                    path, size, old_size, first));
+            }
+        }
+    }
+
+    // Every issue in the transcript, in order of line.
+    pub fn finish(mut self) -> Vec<Issue> {
+        for (path, &line) in &self.visited {
+            if path != "/" && !self.listed.contains_key(path) {
+                self.issues.push(Issue{
+                    line,
+                    message: format!("{} is entered, but never listed by ls in its parent", path),
+                });
+            }
+        }
+        for (path, &line) in &self.listed {
+            if !self.visited.contains_key(path) {
+                self.issues.push(Issue{
+                    line,
+                    message: format!("{} is listed, but never entered, so its contents are \
+                                      unknown", path),
+                });
+            }
+        }
+
+        self.issues.sort_by(|a, b| a.line.cmp(&b.line).then(a.message.cmp(&b.message)));
+        return self.issues;
+    }
+}
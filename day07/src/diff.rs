@@ -0,0 +1,62 @@
+use crate::query::Kind;
+use crate::Directory;
+use std::collections::BTreeMap;
+
+// Something that's different between two snapshots of a filesystem.
+pub enum Change {
+    Added(Kind, usize),
+    Removed(Kind, usize),
+    // A directory or file whose size went from the first number to the second.
+    Resized(Kind, usize, usize),
+}
+
+fn parent(path: &str) -> &str {
+    return match path.rfind('/') {
+        Some(0) => "/",
+        Some(i) => &path[..i],
+        None => "/",
+    };
+}
+
+// Everything under |old| or |new| that was added, removed, or changed size, in order of path. A
+// path that's a file in one and a directory in the other counts as removed and then added. When a
+// whole directory comes or goes, only the directory is listed, not everything in it.
+pub fn diff(old: &Directory, new: &Directory) -> Vec<(String, Change)> {
+    let old: BTreeMap<String, (Kind, usize)> = old.walk()
+        .map(|node| (node.path, (node.kind, node.size)))
+        .collect();
+    let new: BTreeMap<String, (Kind, usize)> = new.walk()
+        .map(|node| (node.path, (node.kind, node.size)))
+        .collect();
+
+    // Whether |path|'s parent is a directory in both snapshots.
+    let kept = |path: &str| {
+        let parent = parent(path);
+        return old.get(parent).map(|e| e.0) == Some(Kind::Dir)
+            && new.get(parent).map(|e| e.0) == Some(Kind::Dir);
+    };
+
+    let mut ret = Vec::new();
+    for (path, &(kind, size)) in &old {
+        if path != "/" && !kept(path) { continue; }
+        match new.get(path) {
+            Some(&(new_kind, new_size)) if new_kind == kind => {
+                if new_size != size {
+                    ret.push((path.clone(), Change::Resized(kind, size, new_size)));
+                }
+            }
+            _ => ret.push((path.clone(), Change::Removed(kind, size))),
+        }
+    }
+    for (path, &(kind, size)) in &new {
+        if path != "/" && !kept(path) { continue; }
+        match old.get(path) {
+            Some(&(old_kind, _)) if old_kind == kind => (),
+            _ => ret.push((path.clone(), Change::Added(kind, size))),
+        }
+    }
+
+    // Sorting is stable, so a removal stays ahead of an addition at the same path.
+    ret.sort_by(|a, b| a.0.cmp(&b.0));
+    return ret;
+}
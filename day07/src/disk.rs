@@ -0,0 +1,96 @@
+use crate::{valid_name, Directory};
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Make sure |name| can only ever refer to something directly inside the directory it's joined to.
+fn check_name(name: &str) -> io::Result<()> {
+    if !valid_name(name) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput, format!("'{}' isn't a valid name", name)));
+    }
+    return Ok(());
+}
+
+// Recreate |dir| on disk under |dest|. Files are sparse, so they have the recorded sizes (which is
+// what `du --apparent-size` reports) without using up that much space. Existing files are only
+// replaced if |overwrite| is set; otherwise finding one is an error.
+pub fn export(dir: &Directory, dest: &Path, overwrite: bool) -> io::Result<()> {
+    std::fs::create_dir_all(dest)?;
+
+    for (name, file) in &dir.files {
+        check_name(name)?;
+        let handle = std::fs::OpenOptions::new()
+            .write(true)
+            .create(overwrite)
+            .truncate(overwrite)
+            .create_new(!overwrite)
+            .open(dest.join(name))?;
+        handle.set_len(file.size as u64)?;
+    }
+    for (name, subdir) in &dir.subdirs {
+        check_name(name)?;
+        export(subdir, &dest.join(name), overwrite)?;
+    }
+    return Ok(());
+}
+
+// A new, empty directory to export into.
+pub fn temp_root() -> io::Result<PathBuf> {
+    let base = std::env::temp_dir();
+    for i in 0.. {
+        let path = base.join(format!("day07-{}-{}", std::process::id(), i));
+        match std::fs::create_dir(&path) {
+            Ok(()) => return Ok(path),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!();
+}
+
+// Walk the real directory |path| and write out the transcript of `cd` and `ls` commands that would
+// explore it, in the same format as the puzzle input. Symlinks are left out, since following them
+// could loop forever.
+pub fn scan(path: &Path) -> io::Result<Vec<String>> {
+    let mut ret = vec!["$ cd /".to_owned()];
+    scan_into(path, &mut ret)?;
+    return Ok(ret);
+}
+
+fn scan_into(path: &Path, out: &mut Vec<String>) -> io::Result<()> {
+    let mut dirs = Vec::new();
+    let mut files = Vec::new();
+
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let name = entry.file_name()
+            .into_string()
+            .map_err(|name| io::Error::new(
+                io::ErrorKind::InvalidData, format!("{:?} isn't valid UTF-8", name)))?;
+        // The transcript format splits on spaces, so there's no way to write these names down.
+        if name.contains(char::is_whitespace) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData, format!("'{}' contains whitespace", name)));
+        }
+
+        let metadata = std::fs::symlink_metadata(entry.path())?;
+        if metadata.is_dir() {
+            dirs.push(name);
+        } else if metadata.is_file() {
+            files.push((name, metadata.len()));
+        }
+    }
+    dirs.sort();
+    files.sort();
+
+    out.push("$ ls".to_owned());
+    for name in &dirs { out.push(format!("dir {}", name)); }
+    for (name, size) in &files { out.push(format!("{} {}", size, name)); }
+
+    for name in &dirs {
+        out.push(format!("$ cd {}", name));
+        scan_into(&path.join(name), out)?;
+        out.push("$ cd ..".to_owned());
+    }
+    return Ok(());
+}
@@ -0,0 +1,32 @@
+use crate::Directory;
+
+// Apply one change to the filesystem, written as one of:
+//   write PATH SIZE    create the file at PATH, or change its size
+//   mkdir PATH         create an empty directory
+//   rm PATH            delete a file or a directory and everything in it
+//   mv PATH DIR        move a file or directory into DIR
+//   rename PATH NAME   rename a file or directory
+fn apply(root: &mut Directory, line: &str) -> Result<(), String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    return match words.as_slice() {
+        ["write", path, size] => {
+            let size = size.parse::<usize>()
+                .map_err(|_| format!("expected a size, found '{}'", size))?;
+            root.write_file(path, size)
+        }
+        ["mkdir", path] => root.make_dir(path),
+        ["rm", path] => root.remove(path).map(|_| ()),
+        ["mv", path, dest] => root.move_to(path, dest),
+        ["rename", path, name] => root.rename(path, name),
+        _ => Err(format!("unknown change '{}'", line)),
+    };
+}
+
+// Apply every change in |lines|, skipping blank lines, failing at the first one that can't be made.
+pub fn run(root: &mut Directory, lines: &[String]) -> Result<(), String> {
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() { continue; }
+        apply(root, line).map_err(|e| format!("line {}: {}", i + 1, e))?;
+    }
+    return Ok(());
+}
@@ -0,0 +1,301 @@
+use crate::{valid_name, Directory, Entry, File};
+use std::collections::BTreeMap;
+
+// Directories are written as objects like
+//   {"size": 94853, "dirs": {"e": {...}}, "files": {"f": 29116, "g": 2557}}
+// with names in sorted order, so the same tree always comes out the same way.
+pub fn export(root: &Directory) -> String {
+    let mut ret = String::new();
+    write_dir(root, 0, &mut ret);
+    ret.push('\n');
+    return ret;
+}
+
+fn quote(text: &str) -> String {
+    let mut ret = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\t' => ret.push_str("\\t"),
+            c if (c as u32) < 0x20 => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    return ret;
+}
+
+fn write_dir(dir: &Directory, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth + 1);
+    let close = "  ".repeat(depth);
+
+    out.push_str(&format!("{{\n{}\"size\": {},\n{}\"dirs\": {{", indent, dir.size, indent));
+    let subdirs: BTreeMap<&String, &Directory> = dir.subdirs.iter().collect();
+    for (i, (name, subdir)) in subdirs.into_iter().enumerate() {
+        if i > 0 { out.push(','); }
+        out.push_str(&format!("\n{}  {}: ", indent, quote(name)));
+        write_dir(subdir, depth + 2, out);
+    }
+    if !dir.subdirs.is_empty() { out.push_str(&format!("\n{}", indent)); }
+
+    out.push_str(&format!("}},\n{}\"files\": {{", indent));
+    let files: BTreeMap<&String, &File> = dir.files.iter().collect();
+    for (i, (name, file)) in files.into_iter().enumerate() {
+        if i > 0 { out.push(','); }
+        out.push_str(&format!("\n{}  {}: {}", indent, quote(name), file.size));
+    }
+    if !dir.files.is_empty() { out.push_str(&format!("\n{}", indent)); }
+    out.push_str(&format!("}}\n{}}}", close));
+}
+
+// Just enough of JSON to read back what |export| writes, and hand edited versions of it.
+// Only numbers and objects can appear in a tree, so the contents of anything else are dropped.
+enum Value {
+    Null,
+    Bool,
+    Number(String),
+    Text,
+    List,
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn describe(&self) -> &'static str {
+        return match self {
+            Value::Null => "null",
+            Value::Bool => "a boolean",
+            Value::Number(_) => "a number",
+            Value::Text => "a string",
+            Value::List => "a list",
+            Value::Object(_) => "an object",
+        };
+    }
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        let line = self.text[..self.pos].iter().filter(|&&b| b == b'\n').count() + 1;
+        return format!("line {}: {}", line, message);
+    }
+
+    fn skip_space(&mut self) {
+        while self.pos < self.text.len() && self.text[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_space();
+        return self.text.get(self.pos).copied();
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() != Some(byte) {
+            return Err(self.error(&format!("expected '{}'", byte as char)));
+        }
+        self.pos += 1;
+        return Ok(());
+    }
+
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        if !self.text[self.pos..].starts_with(word.as_bytes()) {
+            return Err(self.error("expected a value"));
+        }
+        self.pos += word.len();
+        return Ok(value);
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        return match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.list(),
+            Some(b'"') => self.string().map(|_| Value::Text),
+            Some(b't') => self.keyword("true", Value::Bool),
+            Some(b'f') => self.keyword("false", Value::Bool),
+            Some(b'n') => self.keyword("null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                while self.pos < self.text.len()
+                    && matches!(self.text[self.pos], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+                {
+                    self.pos += 1;
+                }
+                let number = std::str::from_utf8(&self.text[start..self.pos]).unwrap();
+                Ok(Value::Number(number.to_owned()))
+            }
+            _ => Err(self.error("expected a value")),
+        };
+    }
+
+    fn list(&mut self) -> Result<Value, String> {
+        self.expect(b'[')?;
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::List);
+        }
+        loop {
+            self.value()?;
+            if self.peek() == Some(b']') {
+                self.pos += 1;
+                return Ok(Value::List);
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect(b'{')?;
+        let mut ret = Vec::new();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(ret));
+        }
+        loop {
+            if self.peek() != Some(b'"') { return Err(self.error("expected a key")); }
+            let key = self.string()?;
+            self.expect(b':')?;
+            ret.push((key, self.value()?));
+            if self.peek() == Some(b'}') {
+                self.pos += 1;
+                return Ok(Value::Object(ret));
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits = self.text.get(self.pos..self.pos + 4)
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+            .ok_or_else(|| self.error("expected four hex digits after \\u"))?;
+        self.pos += 4;
+        return Ok(digits);
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut ret = Vec::new();
+        loop {
+            let Some(&byte) = self.text.get(self.pos) else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&escape) = self.text.get(self.pos) else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.pos += 1;
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex()?;
+                            // Characters outside the BMP are written as a surrogate pair.
+                            if (0xd800..0xdc00).contains(&code)
+                                && self.text[self.pos..].starts_with(b"\\u")
+                            {
+                                self.pos += 2;
+                                let low = self.hex()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(self.error("invalid surrogate pair"));
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            char::from_u32(code)
+                                .ok_or_else(|| self.error("invalid \\u escape"))?
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    let mut buf = [0; 4];
+                    ret.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                _ => ret.push(byte),
+            }
+        }
+        return String::from_utf8(ret).map_err(|_| self.error("string isn't valid UTF-8"));
+    }
+}
+
+// Names come from a single `ls` line in a transcript, so anything that couldn't appear there is
+// turned away rather than making paths ambiguous.
+fn check_name(name: &str, path: &str) -> Result<(), String> {
+    if !valid_name(name) {
+        return Err(format!("{} contains something called '{}', which isn't a valid name",
+                           path, name));
+    }
+    return Ok(());
+}
+
+fn to_dir(value: &Value, path: &str) -> Result<Directory, String> {
+    let Value::Object(fields) = value else {
+        return Err(format!("{} should be an object, not {}", path, value.describe()));
+    };
+
+    let mut ret = Directory::new();
+    let mut size = None;
+    for (key, value) in fields {
+        match (key.as_str(), value) {
+            ("size", Value::Number(n)) => {
+                size = Some(n.parse::<usize>()
+                    .map_err(|_| format!("{} has a bad size {}", path, n))?);
+            }
+            ("dirs", Value::Object(dirs)) => {
+                for (name, subdir) in dirs {
+                    check_name(name, path)?;
+                    let subpath = format!("{}/{}", path.trim_end_matches('/'), name);
+                    let subdir = to_dir(subdir, &subpath)?;
+                    ret.insert(name.clone(), Entry::Dir(subdir))?;
+                }
+            }
+            ("files", Value::Object(files)) => {
+                for (name, file_size) in files {
+                    check_name(name, path)?;
+                    let Value::Number(n) = file_size else {
+                        return Err(format!("{}/{} should be a size, not {}",
+                                           path.trim_end_matches('/'), name, file_size.describe()));
+                    };
+                    let file_size = n.parse::<usize>().map_err(|_| {
+                        format!("{}/{} has a bad size {}", path.trim_end_matches('/'), name, n)
+                    })?;
+                    ret.insert(name.clone(), Entry::File(File::new(file_size)))?;
+                }
+            }
+            ("size" | "dirs" | "files", _) => {
+                return Err(format!("{}'s {} shouldn't be {}", path, key, value.describe()));
+            }
+            _ => return Err(format!("{} has an unexpected field '{}'", path, key)),
+        }
+    }
+
+    // Sizes are worked out from the files; a stored size that disagrees means the file was
+    // edited by hand and something was missed.
+    if let Some(size) = size {
+        if size != ret.size {
+            return Err(format!("{} says its size is {}, but its contents add up to {}",
+                               path, size, ret.size));
+        }
+    }
+    return Ok(ret);
+}
+
+pub fn import(text: &str) -> Result<Directory, String> {
+    let mut parser = Parser{text: text.as_bytes(), pos: 0};
+    let value = parser.value()?;
+    if parser.peek().is_some() { return Err(parser.error("unexpected text after the tree")); }
+    return to_dir(&value, "/");
+}
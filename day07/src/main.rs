@@ -1,3 +1,14 @@
+#![allow(clippy::needless_return)]
+
+mod check;
+mod diff;
+mod disk;
+mod edit;
+mod json;
+mod planner;
+mod query;
+mod render;
+
 use std::collections::HashMap;
 
 struct File {
@@ -6,11 +17,48 @@ struct File {
 
 impl File {
     fn new(size: usize) -> Self {
-        return File{size: size};
+        return File{size};
     }
 }
 
+// Something a directory can hold.
+enum Entry {
+    Dir(Directory),
+    File(File),
+}
+
+impl Entry {
+    fn size(&self) -> usize {
+        return match self {
+            Entry::Dir(dir) => dir.size,
+            Entry::File(file) => file.size,
+        };
+    }
+}
+
+// Whether |name| could be listed by a single line of `ls`, without being mistaken for a path.
+fn valid_name(name: &str) -> bool {
+    return !name.is_empty() && name != "." && name != ".." && !name.contains('/')
+        && !name.contains(char::is_whitespace);
+}
+
+// Split a path like "/a/b/c" (or "a/b/c", which means the same thing) into the directories leading
+// to the last part, and the last part.
+fn split_path(path: &str) -> Result<(Vec<String>, String), String> {
+    let mut parts: Vec<String> = path.split('/')
+        .filter(|part| !part.is_empty())
+        .map(str::to_owned)
+        .collect();
+    if parts.iter().any(|part| part == "." || part == "..") {
+        return Err(format!("{} can't contain . or ..", path));
+    }
+    let name = parts.pop().ok_or_else(|| format!("{} doesn't name anything", path))?;
+    return Ok((parts, name));
+}
 
+// A directory, along with the total size of everything in it. The size is kept up to date by every
+// change made through these methods, as long as changes below the top directory are made through
+// |at| so that the directories above hear about them.
 struct Directory {
     subdirs: HashMap<String, Directory>,
     files: HashMap<String, File>,
@@ -26,110 +74,668 @@ impl Directory {
         };
     }
 
-    // Prepopulate all the sizes on all the directories. Probably not necessary for this challenge,
-    // but I didn't know that when I started.
-    fn calculate_size(&mut self) -> usize {
-        let subdirs_size: usize = self.subdirs.values_mut()
-            .map(Directory::calculate_size)
-            .sum();
+    fn grow(&mut self, delta: isize) {
+        self.size = self.size
+            .checked_add_signed(delta)
+            .expect("directory size went negative");
+    }
 
-        let files_sizes: usize = self.files.values()
-            .map(|file| file.size)
-            .sum();
+    // Run |change| on the directory at |path| below this one. |change| reports how much that
+    // directory grew by, which is passed on to every directory above it.
+    fn at<T>(
+        &mut self,
+        path: &[String],
+        change: impl FnOnce(&mut Directory) -> Result<(T, isize), String>,
+    ) -> Result<T, String> {
+        return self.at_inner(path, change).map(|(ret, _)| ret);
+    }
+
+    fn at_inner<T>(
+        &mut self,
+        path: &[String],
+        change: impl FnOnce(&mut Directory) -> Result<(T, isize), String>,
+    ) -> Result<(T, isize), String> {
+        let Some((name, rest)) = path.split_first() else { return change(self); };
+        let subdir = self.subdirs
+            .get_mut(name)
+            .ok_or_else(|| format!("there's no directory {}", name))?;
+        let (ret, delta) = subdir.at_inner(rest, change)?;
+        self.grow(delta);
+        return Ok((ret, delta));
+    }
 
-        let total = subdirs_size + files_sizes;
-        self.size = total;
-        return total;
+    // Every directory and file in this directory, including itself.
+    fn walk(&self) -> query::Walk<'_> {
+        return query::Walk::new(self);
     }
 
     // Find the total combined size of all subdirectories each of which has a size less than
     // |limit|.
     fn sum_smaller_than(&self, limit: usize) -> usize {
-        let subdirs = self.subdirs.values()
-            .map(|i| i.sum_smaller_than(limit))
+        return self.walk()
+            .filter(|node| node.kind == query::Kind::Dir && node.size <= limit)
+            .map(|node| node.size)
             .sum();
-
-        let self_size = self.size;
-        if self_size > limit { return subdirs; }
-        return self_size + subdirs;
     }
 
     // Find the smallest subdirectory whose size is greater than |limit|.
     fn find_smallest_above(&self, limit: usize) -> usize {
-        if self.size < limit { return usize::MAX; }
-        let subdirs_above: usize = self.subdirs.values()
-            .map(|d| d.find_smallest_above(limit))
+        return self.walk()
+            .filter(|node| node.kind == query::Kind::Dir && node.size >= limit)
+            .map(|node| node.size)
             .min()
             .unwrap_or(usize::MAX);
+    }
 
-        return std::cmp::min(self.size, subdirs_above);
+    // Add an empty directory called |name|, unless there already is one.
+    fn add_dir(&mut self, name: String) {
+        self.subdirs.entry(name).or_insert_with(Directory::new);
     }
 
-    fn add_dir(&mut self, name: String, dir: Directory) {
-        self.subdirs.insert(name, dir);
+    // Add |file|, replacing any file with the same name. Returns how much this directory grew by.
+    fn add_file(&mut self, name: String, file: File) -> isize {
+        let new_size = file.size as isize;
+        let old_size = self.files.insert(name, file).map_or(0, |old| old.size as isize);
+        self.grow(new_size - old_size);
+        return new_size - old_size;
     }
-    fn add_file(&mut self, name: String, file: File) {
-        self.files.insert(name, file);
+
+    // Add |entry| under |name|, which mustn't be in use. Returns how much this directory grew by.
+    fn insert(&mut self, name: String, entry: Entry) -> Result<isize, String> {
+        if self.subdirs.contains_key(&name) || self.files.contains_key(&name) {
+            return Err(format!("{} already exists", name));
+        }
+        let size = entry.size() as isize;
+        match entry {
+            Entry::Dir(dir) => { self.subdirs.insert(name, dir); }
+            Entry::File(file) => { self.files.insert(name, file); }
+        }
+        self.grow(size);
+        return Ok(size);
     }
 
-    // Add a file or directory based upon |line| to this directory.
-    fn parse_line(&mut self, line: &String) {
-        let mut parts = line.split(" ");
-        let leader = parts.next().expect("expected a leader");
-        let name = parts.next().expect("expected a name").to_owned();
-        assert_eq!(parts.next(), None);
+    // Take the file or directory called |name| out. Returns it and how much this directory grew by.
+    fn take(&mut self, name: &str) -> Result<(Entry, isize), String> {
+        let entry = match (self.subdirs.remove(name), self.files.remove(name)) {
+            (Some(dir), _) => Entry::Dir(dir),
+            (None, Some(file)) => Entry::File(file),
+            (None, None) => return Err(format!("there's no {}", name)),
+        };
+        let size = entry.size() as isize;
+        self.grow(-size);
+        return Ok((entry, -size));
+    }
 
-        if leader == "dir" {
-            self.add_dir(name, Directory::new());
-            return;
+    // Create or resize the file at |path|.
+    fn write_file(&mut self, path: &str, size: usize) -> Result<(), String> {
+        let (dirs, name) = split_path(path)?;
+        return self.at(&dirs, |dir| {
+            if dir.subdirs.contains_key(&name) {
+                return Err(format!("{} is a directory", path));
+            }
+            Ok(((), dir.add_file(name, File::new(size))))
+        });
+    }
+
+    // Create an empty directory at |path|, unless there already is one.
+    fn make_dir(&mut self, path: &str) -> Result<(), String> {
+        let (dirs, name) = split_path(path)?;
+        return self.at(&dirs, |dir| {
+            if dir.files.contains_key(&name) { return Err(format!("{} is a file", path)); }
+            dir.add_dir(name);
+            Ok(((), 0))
+        });
+    }
+
+    // Delete the file or directory at |path|, returning what was there.
+    fn remove(&mut self, path: &str) -> Result<Entry, String> {
+        let (dirs, name) = split_path(path)?;
+        return self.at(&dirs, |dir| dir.take(&name));
+    }
+
+    // Give the file or directory at |path| a new name, keeping it in the same directory.
+    fn rename(&mut self, path: &str, new_name: &str) -> Result<(), String> {
+        let (dirs, name) = split_path(path)?;
+        if !valid_name(new_name) {
+            return Err(format!("{} isn't a valid name", new_name));
+        }
+        return self.at(&dirs, |dir| {
+            // Check the new name is free before taking anything out, so a failed rename changes
+            // nothing.
+            if new_name != name
+                && (dir.subdirs.contains_key(new_name) || dir.files.contains_key(new_name))
+            {
+                return Err(format!("{} already exists", new_name));
+            }
+            let (entry, _) = dir.take(&name)?;
+            dir.insert(new_name.to_owned(), entry)?;
+            Ok(((), 0))
+        });
+    }
+
+    // Move the file or directory at |path| into the directory |dest|, keeping its name.
+    fn move_to(&mut self, path: &str, dest: &str) -> Result<(), String> {
+        let (dirs, name) = split_path(path)?;
+        let dest_dirs: Vec<String> = dest.split('/')
+            .filter(|part| !part.is_empty())
+            .map(str::to_owned)
+            .collect();
+
+        let mut moved = dirs.clone();
+        moved.push(name.clone());
+        if dest_dirs.starts_with(&moved) {
+            return Err(format!("can't move {} inside itself", path));
+        }
+        // Make sure there's somewhere to put it before taking it out.
+        self.at(&dest_dirs, |dir| {
+            if dir.subdirs.contains_key(&name) || dir.files.contains_key(&name) {
+                return Err(format!("{}/{} already exists", dest.trim_end_matches('/'), name));
+            }
+            Ok(((), 0))
+        })?;
+
+        let entry = self.at(&dirs, |dir| dir.take(&name))?;
+        return self.at(&dest_dirs, |dir| Ok(((), dir.insert(name, entry)?)));
+    }
+}
+
+// A line of `ls` output.
+enum Listing {
+    Dir(String),
+    File(String, usize),
+}
+
+impl Listing {
+    fn parse(line: &str) -> Result<Self, String> {
+        let mut parts = line.split(' ');
+        let leader = parts.next().ok_or("expected a leader")?;
+        let name = parts.next().ok_or("expected a name")?.to_owned();
+        if parts.next().is_some() {
+            return Err(format!("unexpected text after the name in '{}'", line));
+        }
+        if !valid_name(&name) { return Err(format!("'{}' isn't a valid name", name)); }
+
+        if leader == "dir" { return Ok(Listing::Dir(name)); }
+
+        let size = leader.parse::<usize>()
+            .map_err(|_| format!("expected 'dir' or a size, found '{}'", leader))?;
+        return Ok(Listing::File(name, size));
+    }
+}
+
+// Replays a shell transcript of `cd` and `ls` commands to rebuild the filesystem it explored.
+struct Shell {
+    root: Directory,
+    // The names of the directories leading from the root to the current directory.
+    cwd: Vec<String>,
+    // Whether the lines being read are the output of `ls`.
+    listing: bool,
+    // The line being run.
+    line: usize,
+    log: check::Log,
+}
+
+impl Shell {
+    fn new() -> Self {
+        return Shell{
+            root: Directory::new(),
+            cwd: Vec::new(),
+            listing: false,
+            line: 0,
+            log: check::Log::default(),
+        };
+    }
+
+    // The full path of |name| in the current directory.
+    fn path(&self, name: &str) -> String {
+        let mut ret = String::new();
+        for dir in self.cwd.iter().map(String::as_str).chain([name]) {
+            ret.push('/');
+            ret.push_str(dir);
+        }
+        return ret;
+    }
+
+    // Change directory to |path|, which may be absolute or relative and may contain "..".
+    // Directories which haven't been listed yet are created as they're entered.
+    fn cd(&mut self, path: &str) {
+        if path.starts_with('/') {
+            self.cwd.clear();
+            self.log.visit("/".to_owned(), self.line);
+        }
+
+        for name in path.split('/') {
+            match name {
+                "" | "." => continue,
+                // Like a real shell, going up from the root stays at the root.
+                ".." => { self.cwd.pop(); }
+                _ => {
+                    let full = self.path(name);
+                    let was_file = self.root.at(&self.cwd, |dir| {
+                        let old = dir.files.contains_key(name).then(|| dir.take(name));
+                        dir.add_dir(name.to_owned());
+                        Ok(match old {
+                            Some(taken) => (true, taken?.1),
+                            None => (false, 0),
+                        })
+                    }).expect("cwd should exist");
+
+                    if was_file {
+                        self.log.conflict(self.line, format!(
+                            "{} was listed as a file, but is entered like a directory", full));
+                    }
+                    self.log.visit(full, self.line);
+                    self.cwd.push(name.to_owned());
+                }
+            }
+        }
+    }
+
+    // Add a file or directory based upon |line|, a line of `ls` output, to the current directory.
+    // If a name turns up as both a file and a directory, the directory wins.
+    fn list(&mut self, line: &str) -> Result<(), String> {
+        match Listing::parse(line)? {
+            Listing::Dir(name) => {
+                let full = self.path(&name);
+                let was_file = self.root.at(&self.cwd, |dir| {
+                    let old = dir.files.contains_key(&name).then(|| dir.take(&name));
+                    dir.add_dir(name);
+                    Ok(match old {
+                        Some(taken) => (true, taken?.1),
+                        None => (false, 0),
+                    })
+                }).expect("cwd should exist");
+
+                if was_file {
+                    self.log.conflict(self.line, format!(
+                        "{} was listed as a file, and now as a directory", full));
+                }
+                self.log.list_dir(full, self.line);
+            }
+            Listing::File(name, size) => {
+                let full = self.path(&name);
+                let is_dir = self.root.at(&self.cwd, |dir| {
+                    if dir.subdirs.contains_key(&name) { return Ok((true, 0)); }
+                    Ok((false, dir.add_file(name, File::new(size))))
+                }).expect("cwd should exist");
+                if is_dir {
+                    self.log.conflict(self.line, format!(
+                        "{} is a directory, but is listed as a file", full));
+                    return Ok(());
+                }
+                self.log.list_file(full, size, self.line);
+            }
+        }
+        return Ok(());
+    }
+
+    fn execute(&mut self, line: &str) -> Result<(), String> {
+        let Some(command) = line.strip_prefix("$ ") else {
+            if !self.listing {
+                return Err(format!("'{}' isn't a command or the output of ls", line));
+            }
+            return self.list(line);
+        };
+
+        self.listing = false;
+        let mut words = command.split_whitespace();
+        return match (words.next(), words.next(), words.next()) {
+            (Some("cd"), Some(path), None) => {
+                self.cd(path);
+                Ok(())
+            }
+            (Some("cd"), _, _) => Err("cd needs exactly one directory".to_owned()),
+            (Some("ls"), None, _) => {
+                self.listing = true;
+                Ok(())
+            }
+            (Some("ls"), _, _) => Err("ls doesn't take any arguments".to_owned()),
+            _ => Err(format!("unknown command '{}'", command)),
+        };
+    }
+
+    // Run every line of a transcript, failing at the first line that doesn't make sense.
+    // Contradictions and gaps in the transcript aren't failures; they're collected by |finish|.
+    fn run(&mut self, lines: &[String]) -> Result<(), String> {
+        for (i, line) in lines.iter().enumerate() {
+            self.line = i + 1;
+            self.execute(line).map_err(|e| format!("line {}: {}", i + 1, e))?;
         }
+        return Ok(());
+    }
 
-        let size = leader.parse::<usize>().expect("expected 'dir' or a size");
-        self.add_file(name, File::new(size));
+    // The filesystem the transcript explored, and everything that's suspicious about it.
+    fn finish(self) -> (Directory, Vec<check::Issue>) {
+        return (self.root, self.log.finish());
     }
 }
 
-fn parse(dir: &mut Directory, lines: &mut dyn Iterator<Item=&String>) {
-    let command = lines.next().expect("Expected a line.");
-    assert_eq!(command, "$ ls");
+enum Output {
+    Answers,
+    Tree,
+    Du,
+    Query(query::Query),
+    Report,
+    Plan,
+    // Report every inconsistency in the transcript.
+    Check,
+    // Recreate the filesystem under the given directory, or a new temporary one.
+    Export(Option<std::path::PathBuf>),
+    // Write a transcript for a real directory instead of reading one.
+    Scan(std::path::PathBuf),
+    // Print the tree as JSON.
+    Json,
+    // Compare the tree against an earlier snapshot saved as JSON.
+    Diff(String),
+}
+
+struct Options {
+    output: Output,
+    style: render::Style,
+    // A JSON snapshot to read the filesystem from, instead of a transcript on stdin.
+    load: Option<String>,
+    // Where to save a JSON snapshot of the filesystem once it's been read and edited.
+    save: Option<String>,
+    // A file of changes to make to the filesystem once the transcript has been read.
+    edits: Option<String>,
+    disk_size: usize,
+    // How much space has to be free in total.
+    required_size: usize,
+    // The most files and directories a deletion plan may delete, and how many plans to show.
+    max_items: usize,
+    alternatives: usize,
+}
 
-    loop {
-        let line = lines.next();
-        if line.is_none() { return; }
-        let line = line.unwrap();
+impl Options {
+    fn parse(args: impl Iterator<Item = String>) -> Self {
+        let style = render::Style{sort: render::Sort::Name, human: false, depth: None};
+        let mut ret = Options{
+            output: Output::Answers,
+            style,
+            load: None,
+            save: None,
+            edits: None,
+            disk_size: 70000000,
+            required_size: 30000000,
+            max_items: 3,
+            alternatives: 5,
+        };
+        fn number(args: &mut dyn Iterator<Item = String>, what: &str) -> usize {
+            return args.next()
+                .unwrap_or_else(|| panic!("{} needs a number", what))
+                .parse::<usize>()
+                .unwrap_or_else(|_| panic!("{} should be a number", what));
+        }
+        let mut args = args.skip(1);
 
-        if line == "$ cd .." { return; }
-        if line.starts_with("$ cd ") {
-            let dirname: &str = &line[5..];
-            let mut subdir = dir.subdirs
-                .get_mut(dirname)
-                .expect("dir not found");
-            parse(&mut subdir, lines);
-            continue;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--tree" => ret.output = Output::Tree,
+                "--du" => ret.output = Output::Du,
+                "--human" => ret.style.human = true,
+                "--report" => ret.output = Output::Report,
+                "--check" => ret.output = Output::Check,
+                "--edit" => ret.edits = Some(args.next().expect("--edit needs a file")),
+                "--query" => {
+                    let text = args.next().expect("--query needs a query");
+                    let query = query::Query::parse(&text)
+                        .unwrap_or_else(|e| panic!("bad query: {}", e));
+                    ret.output = Output::Query(query);
+                }
+                "--sort" => {
+                    ret.style.sort = match args.next().as_deref() {
+                        Some("name") => render::Sort::Name,
+                        Some("size") => render::Sort::Size,
+                        _ => panic!("--sort needs 'name' or 'size'"),
+                    };
+                }
+                "--depth" => ret.style.depth = Some(number(&mut args, "--depth")),
+                "--disk" => ret.disk_size = number(&mut args, "--disk"),
+                "--required" => ret.required_size = number(&mut args, "--required"),
+                "--plan" => ret.output = Output::Plan,
+                "--export" => {
+                    let dir = args.next().expect("--export needs a directory");
+                    ret.output = Output::Export(Some(dir.into()));
+                }
+                "--export-temp" => ret.output = Output::Export(None),
+                "--scan" => {
+                    let dir = args.next().expect("--scan needs a directory");
+                    ret.output = Output::Scan(dir.into());
+                }
+                "--json" => ret.output = Output::Json,
+                "--load" => ret.load = Some(args.next().expect("--load needs a file")),
+                "--save" => ret.save = Some(args.next().expect("--save needs a file")),
+                "--diff" => {
+                    let path = args.next().expect("--diff needs a file");
+                    ret.output = Output::Diff(path);
+                }
+                "--max-items" => ret.max_items = number(&mut args, "--max-items"),
+                "--alternatives" => {
+                    ret.alternatives = Some(number(&mut args, "--alternatives"))
+                        .filter(|&n| n > 0)
+                        .expect("--alternatives should be positive");
+                }
+                _ => panic!("Unknown argument {}", arg),
+            }
         }
-        if !line.starts_with("?") {
-            dir.parse_line(line);
-            continue;
+
+        return ret;
+    }
+}
+
+// Answer some questions about the tree that the puzzle didn't ask.
+fn report(root: &Directory, style: &render::Style) {
+    let largest_file = root.walk()
+        .filter(|node| node.kind == query::Kind::File)
+        .max_by_key(|node| node.size);
+    if let Some(node) = largest_file {
+        println!("largest file:      {} ({})", node.path, style.size(node.size));
+    }
+
+    let deepest_dir = root.walk()
+        .filter(|node| node.kind == query::Kind::Dir)
+        .max_by_key(|node| node.depth);
+    if let Some(node) = deepest_dir {
+        println!("deepest directory: {} (depth {})", node.path, node.depth);
+    }
+
+    let mut by_name: HashMap<&str, Vec<String>> = HashMap::new();
+    for node in root.walk().skip(1) {
+        by_name.entry(node.name).or_default().push(node.path);
+    }
+    let mut duplicates: Vec<(&str, Vec<String>)> = by_name.into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect();
+    duplicates.sort();
+
+    println!("duplicate names:   {}", duplicates.len());
+    for (name, paths) in duplicates {
+        println!("  {}: {}", name, paths.join(" "));
+    }
+}
+
+fn print_plans(root: &Directory, need_size: usize, options: &Options) {
+    if need_size == 0 {
+        println!("nothing needs to be deleted, there's already enough space");
+        return;
+    }
+
+    let plans = planner::plan(root, need_size, options.max_items, options.alternatives);
+    if plans.is_empty() {
+        println!("can't free {} bytes by deleting at most {} things",
+                 need_size, options.max_items);
+        return;
+    }
+
+    println!("need to free {}", options.style.size(need_size));
+    for (i, plan) in plans.iter().enumerate() {
+        println!("{}. delete {} (over by {})", i + 1,
+                 options.style.size(plan.total), options.style.size(plan.total - need_size));
+        for node in &plan.nodes {
+            println!("     {}\t{}", options.style.size(node.size), node.path);
+        }
+    }
+}
+
+// Read a tree saved with --save or --json, or give up.
+fn read_snapshot(path: &str) -> Directory {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("can't read {}: {}", path, e);
+        std::process::exit(1);
+    });
+    return json::import(&text).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        std::process::exit(1);
+    });
+}
+
+fn print_diff(old: &Directory, new: &Directory, style: &render::Style) {
+    let changes = diff::diff(old, new);
+    if changes.is_empty() {
+        println!("no changes");
+        return;
+    }
+
+    let kind = |kind: query::Kind| if kind == query::Kind::Dir { "dir " } else { "file" };
+    for (path, change) in changes {
+        match change {
+            diff::Change::Added(k, size) => {
+                println!("added   {} {} ({})", kind(k), path, style.size(size));
+            }
+            diff::Change::Removed(k, size) => {
+                println!("removed {} {} ({})", kind(k), path, style.size(size));
+            }
+            diff::Change::Resized(k, from, to) => {
+                let verb = if to > from { "grew   " } else { "shrank " };
+                println!("{} {} {} ({} -> {})",
+                         verb, kind(k), path, style.size(from), style.size(to));
+            }
         }
     }
 }
 
 fn main() {
-    let lines = std::io::stdin()
-        .lines()
-        .map(Result::unwrap)
-        .skip(1)
-        .collect::<Vec<String>>();
-
-    let mut root = Directory::new();
-    parse(&mut root, &mut lines.iter());
-    let disk_size = 70000000usize;
-    let used_size = root.calculate_size();
-    let free_size = disk_size - used_size;
-    let need_size = 30000000usize - free_size;
+    let options = Options::parse(std::env::args());
+
+    if let Output::Scan(path) = &options.output {
+        let transcript = disk::scan(path).unwrap_or_else(|e| {
+            eprintln!("can't scan {}: {}", path.display(), e);
+            std::process::exit(1);
+        });
+        for line in transcript { println!("{}", line); }
+        return;
+    }
+
+    let (mut root, issues) = match &options.load {
+        Some(path) => (read_snapshot(path), Vec::new()),
+        None => {
+            let lines = std::io::stdin()
+                .lines()
+                .map(Result::unwrap)
+                .collect::<Vec<String>>();
+
+            let mut shell = Shell::new();
+            if let Err(e) = shell.run(&lines) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            shell.finish()
+        }
+    };
+
+    if let Some(path) = &options.edits {
+        let text = std::fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("can't read {}: {}", path, e);
+            std::process::exit(1);
+        });
+        let lines: Vec<String> = text.lines().map(str::to_owned).collect();
+        if let Err(e) = edit::run(&mut root, &lines) {
+            eprintln!("{}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+    let used_size = root.size;
+
+    if let Some(path) = &options.save {
+        if let Err(e) = std::fs::write(path, json::export(&root)) {
+            eprintln!("can't save {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+
+    if let Output::Check = options.output {
+        for issue in &issues { println!("line {}: {}", issue.line, issue.message); }
+        if !issues.is_empty() { std::process::exit(1); }
+        println!("no problems found");
+        return;
+    }
+    for issue in &issues { eprintln!("warning: line {}: {}", issue.line, issue.message); }
+
+    match options.output {
+        Output::Answers => (),
+        Output::Tree => {
+            for line in options.style.tree(&root) { println!("{}", line); }
+            return;
+        }
+        Output::Du => {
+            for line in options.style.du(&root) { println!("{}", line); }
+            return;
+        }
+        Output::Query(query) => {
+            for node in query.run(&root) {
+                println!("{}\t{}", options.style.size(node.size), node.path);
+            }
+            return;
+        }
+        Output::Report => {
+            report(&root, &options.style);
+            return;
+        }
+        Output::Export(dest) => {
+            // A new temporary directory should be empty, so anything already there is a mistake.
+            let (dest, overwrite) = match dest {
+                Some(dest) => (Ok(dest.clone()), true),
+                None => (disk::temp_root(), false),
+            };
+            let result = dest.and_then(|dest| disk::export(&root, &dest, overwrite).map(|_| dest));
+            match result {
+                Ok(dest) => println!("{}", dest.display()),
+                Err(e) => {
+                    eprintln!("can't export: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Output::Json => {
+            print!("{}", json::export(&root));
+            return;
+        }
+        Output::Diff(path) => {
+            print_diff(&read_snapshot(&path), &root, &options.style);
+            return;
+        }
+        Output::Plan | Output::Scan(_) | Output::Check => (),
+    }
+
+    if used_size > options.disk_size {
+        eprintln!("{} bytes are used, but the disk only holds {}", used_size, options.disk_size);
+        std::process::exit(1);
+    }
+    let free_size = options.disk_size - used_size;
+    let need_size = options.required_size.saturating_sub(free_size);
+
+    if let Output::Plan = options.output {
+        print_plans(&root, need_size, &options);
+        return;
+    }
 
     println!("total:              {}", root.size);
     println!("< 100000:           {}", root.sum_smaller_than(100000));
     println!("extra space needed: {}", need_size);
-    println!("smallest to delete: {}", root.find_smallest_above(need_size));
+    if need_size == 0 {
+        println!("smallest to delete: nothing, there's already enough space");
+    } else {
+        println!("smallest to delete: {}", root.find_smallest_above(need_size));
+    }
 }
@@ -0,0 +1,78 @@
+use crate::query::{Kind, Node};
+use crate::Directory;
+
+// A set of files and directories which could be deleted together. None of them is inside another,
+// so |total| is how much space deleting them all frees.
+pub struct Deletion<'a> {
+    pub nodes: Vec<Node<'a>>,
+    pub total: usize,
+}
+
+fn inside(node: &Node, dir: &Node) -> bool {
+    return dir.kind == Kind::Dir && node.path.starts_with(&format!("{}/", dir.path));
+}
+
+struct Search<'a> {
+    // Everything that could be deleted, biggest first.
+    candidates: Vec<Node<'a>>,
+    need: usize,
+    max_items: usize,
+    keep: usize,
+    // The best deletions found so far, smallest total first.
+    best: Vec<Deletion<'a>>,
+}
+
+impl<'a> Search<'a> {
+    // Anything at least this big can't make it into the best deletions.
+    fn bound(&self) -> usize {
+        if self.best.len() < self.keep { return usize::MAX; }
+        return self.best.last().unwrap().total;
+    }
+
+    fn record(&mut self, chosen: &[usize], total: usize) {
+        let nodes = chosen.iter().map(|&i| self.candidates[i].clone()).collect();
+        let at = self.best.partition_point(|d| d.total <= total);
+        self.best.insert(at, Deletion{nodes, total});
+        self.best.truncate(self.keep);
+    }
+
+    // Try adding each candidate from |next| on to the ones already |chosen|.
+    fn extend(&mut self, chosen: &mut Vec<usize>, total: usize, next: usize) {
+        if total >= self.need {
+            if total < self.bound() { self.record(chosen, total); }
+            return;
+        }
+        if chosen.len() == self.max_items { return; }
+
+        for i in next..self.candidates.len() {
+            let size = self.candidates[i].size;
+            // Candidates only get smaller, so if filling every free slot with this one isn't
+            // enough, nothing after it will be either.
+            let slots = self.max_items - chosen.len();
+            if total.saturating_add(size.saturating_mul(slots)) < self.need { return; }
+            if total.saturating_add(size) >= self.bound() { continue; }
+
+            let candidate = &self.candidates[i];
+            let nested = chosen.iter().any(|&j| {
+                let other = &self.candidates[j];
+                inside(candidate, other) || inside(other, candidate)
+            });
+            if nested { continue; }
+
+            chosen.push(i);
+            self.extend(chosen, total + size, i + 1);
+            chosen.pop();
+        }
+    }
+}
+
+// Find the |keep| cheapest ways to free at least |need| bytes by deleting at most |max_items|
+// files and directories, cheapest first. Cheapest means the fewest bytes deleted in total.
+pub fn plan(root: &Directory, need: usize, max_items: usize, keep: usize) -> Vec<Deletion<'_>> {
+    let mut candidates: Vec<Node> = root.walk().skip(1).collect();
+    candidates.sort_by(|a, b| b.size.cmp(&a.size).then(a.path.cmp(&b.path)));
+
+    let mut search = Search{candidates, need, max_items, keep, best: Vec::new()};
+    search.extend(&mut Vec::new(), 0, 0);
+    return search.best;
+}
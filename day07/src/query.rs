@@ -0,0 +1,215 @@
+use crate::Directory;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Dir,
+    File,
+}
+
+// A file or directory found while walking a tree.
+#[derive(Clone)]
+pub struct Node<'a> {
+    pub kind: Kind,
+    // The full path, like "/a/e/i". The root is "/".
+    pub path: String,
+    pub name: &'a str,
+    // How many directories down from the root this is. The root itself is at depth 0.
+    pub depth: usize,
+    pub size: usize,
+    dir: Option<&'a Directory>,
+}
+
+// Walks every directory and file under a root, depth first. A directory comes before its contents,
+// which come in order of name.
+pub struct Walk<'a> {
+    pending: Vec<Node<'a>>,
+}
+
+impl<'a> Walk<'a> {
+    pub fn new(root: &'a Directory) -> Self {
+        let node = Node{
+            kind: Kind::Dir,
+            path: "/".to_owned(),
+            name: "/",
+            depth: 0,
+            size: root.size,
+            dir: Some(root),
+        };
+        return Walk{pending: vec![node]};
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Node<'a>> {
+        let node = self.pending.pop()?;
+        let Some(dir) = node.dir else { return Some(node); };
+
+        let prefix = node.path.trim_end_matches('/');
+        let child = |kind: Kind, name: &'a String, size: usize, dir: Option<&'a Directory>| Node{
+            kind,
+            path: format!("{}/{}", prefix, name),
+            name: name.as_str(),
+            depth: node.depth + 1,
+            size,
+            dir,
+        };
+
+        let mut children: Vec<Node<'a>> = dir.subdirs.iter()
+            .map(|(name, d)| child(Kind::Dir, name, d.size, Some(d)))
+            .chain(dir.files.iter().map(|(name, f)| child(Kind::File, name, f.size, None)))
+            .collect();
+        // The stack pops from the end, so put the first name last.
+        children.sort_by(|a, b| b.name.cmp(a.name));
+        self.pending.extend(children);
+
+        return Some(node);
+    }
+}
+
+// Whether |text| matches |pattern|, where '*' matches any run of characters and '?' matches any
+// single character.
+pub fn glob(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // matches[j] is whether the pattern so far matches the first j characters of the text.
+    let mut matches = vec![false; text.len() + 1];
+    matches[0] = true;
+    for p in pattern {
+        let mut next = vec![false; text.len() + 1];
+        for j in 0..=text.len() {
+            next[j] = match p {
+                '*' => matches[j] || (j > 0 && next[j - 1]),
+                '?' => j > 0 && matches[j - 1],
+                _ => j > 0 && matches[j - 1] && text[j - 1] == p,
+            };
+        }
+        matches = next;
+    }
+    return matches[text.len()];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    Glob,
+}
+
+#[derive(Debug, Clone)]
+enum Condition {
+    Size(Op, usize),
+    Depth(Op, usize),
+    Name(Op, String),
+    Path(Op, String),
+    Type(Kind),
+}
+
+fn compare<T: Ord>(op: Op, a: T, b: T) -> bool {
+    return match op {
+        Op::Less => a < b,
+        Op::LessEqual => a <= b,
+        Op::Greater => a > b,
+        Op::GreaterEqual => a >= b,
+        Op::Equal => a == b,
+        Op::NotEqual => a != b,
+        Op::Glob => unreachable!("numbers can't be globbed"),
+    };
+}
+
+fn compare_text(op: Op, text: &str, value: &str) -> bool {
+    return match op {
+        Op::Equal => text == value,
+        Op::NotEqual => text != value,
+        Op::Glob => glob(value, text),
+        _ => unreachable!("text can only be compared with =, != or ~"),
+    };
+}
+
+impl Condition {
+    fn parse(field: &str, op: &str, value: &str) -> Result<Self, String> {
+        let op = match op {
+            "<" => Op::Less,
+            "<=" => Op::LessEqual,
+            ">" => Op::Greater,
+            ">=" => Op::GreaterEqual,
+            "=" | "==" => Op::Equal,
+            "!=" => Op::NotEqual,
+            "~" => Op::Glob,
+            _ => return Err(format!("unknown operator '{}'", op)),
+        };
+        let number = || -> Result<usize, String> {
+            if op == Op::Glob { return Err(format!("{} can't be matched with ~", field)); }
+            return value.parse::<usize>()
+                .map_err(|_| format!("expected a number for {}, found '{}'", field, value));
+        };
+        let text = || -> Result<String, String> {
+            if !matches!(op, Op::Equal | Op::NotEqual | Op::Glob) {
+                return Err(format!("{} can only be compared with =, != or ~", field));
+            }
+            return Ok(value.to_owned());
+        };
+
+        return match field {
+            "size" => Ok(Condition::Size(op, number()?)),
+            "depth" => Ok(Condition::Depth(op, number()?)),
+            "name" => Ok(Condition::Name(op, text()?)),
+            "path" => Ok(Condition::Path(op, text()?)),
+            "type" => match (op, value) {
+                (Op::Equal, "dir") => Ok(Condition::Type(Kind::Dir)),
+                (Op::Equal, "file") => Ok(Condition::Type(Kind::File)),
+                _ => Err("type can only be 'type = dir' or 'type = file'".to_owned()),
+            },
+            _ => Err(format!("unknown field '{}'", field)),
+        };
+    }
+
+    fn matches(&self, node: &Node) -> bool {
+        return match self {
+            Condition::Size(op, size) => compare(*op, node.size, *size),
+            Condition::Depth(op, depth) => compare(*op, node.depth, *depth),
+            Condition::Name(op, name) => compare_text(*op, node.name, name),
+            Condition::Path(op, path) => compare_text(*op, &node.path, path),
+            Condition::Type(kind) => node.kind == *kind,
+        };
+    }
+}
+
+// Conditions on files and directories, all of which have to hold, like
+// "type = dir and size <= 100000" or "name ~ *.txt and depth <= 2".
+#[derive(Debug, Clone)]
+pub struct Query {
+    conditions: Vec<Condition>,
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let mut conditions = Vec::new();
+
+        for clause in words.split(|&w| w == "and") {
+            match clause {
+                [field, op, value] => conditions.push(Condition::parse(field, op, value)?),
+                [] => return Err("expected a condition like 'size < 100000'".to_owned()),
+                _ => return Err(format!("expected 'field op value', found '{}'", clause.join(" "))),
+            }
+        }
+
+        return Ok(Query{conditions});
+    }
+
+    pub fn matches(&self, node: &Node) -> bool {
+        return self.conditions.iter().all(|c| c.matches(node));
+    }
+
+    // Every file and directory under |root| that matches.
+    pub fn run<'a>(&'a self, root: &'a Directory) -> impl Iterator<Item = Node<'a>> + 'a {
+        return Walk::new(root).filter(move |node| self.matches(node));
+    }
+}
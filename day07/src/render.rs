@@ -0,0 +1,113 @@
+use crate::{Directory, File};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    Name,
+    // Biggest first, with ties broken by name.
+    Size,
+}
+
+pub struct Style {
+    pub sort: Sort,
+    // Sizes like "4.0M" rather than a number of bytes.
+    pub human: bool,
+    // How many levels below the root to show. Anything deeper still counts towards sizes.
+    pub depth: Option<usize>,
+}
+
+enum Entry<'a> {
+    Dir(&'a Directory),
+    File(&'a File),
+}
+
+impl Entry<'_> {
+    fn size(&self) -> usize {
+        return match self {
+            Entry::Dir(dir) => dir.size,
+            Entry::File(file) => file.size,
+        };
+    }
+}
+
+// Everything directly inside |dir|, in the order |sort| asks for.
+fn entries(dir: &Directory, sort: Sort) -> Vec<(&String, Entry<'_>)> {
+    let mut ret: Vec<(&String, Entry)> = dir.subdirs.iter()
+        .map(|(name, d)| (name, Entry::Dir(d)))
+        .chain(dir.files.iter().map(|(name, f)| (name, Entry::File(f))))
+        .collect();
+
+    match sort {
+        Sort::Name => ret.sort_by(|a, b| a.0.cmp(b.0)),
+        Sort::Size => ret.sort_by(|a, b| b.1.size().cmp(&a.1.size()).then(a.0.cmp(b.0))),
+    }
+    return ret;
+}
+
+// Format |size| the way `du -h` does, rounding up to one decimal place below 10 and to whole units
+// above.
+pub fn human(size: usize) -> String {
+    if size < 1024 { return size.to_string(); }
+
+    let mut value = size as f64;
+    let mut unit = ' ';
+    for next in ['K', 'M', 'G', 'T', 'P'] {
+        if value < 1024.0 { break; }
+        value /= 1024.0;
+        unit = next;
+    }
+
+    if value < 10.0 { return format!("{:.1}{}", (value * 10.0).ceil() / 10.0, unit); }
+    return format!("{:.0}{}", value.ceil(), unit);
+}
+
+impl Style {
+    pub fn size(&self, size: usize) -> String {
+        if self.human { return human(size); }
+        return size.to_string();
+    }
+
+    fn too_deep(&self, depth: usize) -> bool {
+        return self.depth.is_some_and(|limit| depth > limit);
+    }
+
+    // Draw |root| as an indented tree, like the example in the puzzle.
+    pub fn tree(&self, root: &Directory) -> Vec<String> {
+        let mut ret = vec![format!("- / (dir, size={})", self.size(root.size))];
+        self.tree_into(root, 1, &mut ret);
+        return ret;
+    }
+
+    fn tree_into(&self, dir: &Directory, depth: usize, out: &mut Vec<String>) {
+        if self.too_deep(depth) { return; }
+
+        let indent = "  ".repeat(depth);
+        for (name, entry) in entries(dir, self.sort) {
+            let size = self.size(entry.size());
+            match entry {
+                Entry::Dir(subdir) => {
+                    out.push(format!("{}- {} (dir, size={})", indent, name, size));
+                    self.tree_into(subdir, depth + 1, out);
+                }
+                Entry::File(_) => out.push(format!("{}- {} (file, size={})", indent, name, size)),
+            }
+        }
+    }
+
+    // List every file and directory with its full path, like `du -a`: the contents of a directory
+    // come before the directory itself.
+    pub fn du(&self, root: &Directory) -> Vec<String> {
+        let mut ret = Vec::new();
+        self.du_into(root, "", 0, &mut ret);
+        ret.push(format!("{}\t/", self.size(root.size)));
+        return ret;
+    }
+
+    fn du_into(&self, dir: &Directory, path: &str, depth: usize, out: &mut Vec<String>) {
+        for (name, entry) in entries(dir, self.sort) {
+            let path = format!("{}/{}", path, name);
+            if let Entry::Dir(subdir) = entry { self.du_into(subdir, &path, depth + 1, out); }
+            if self.too_deep(depth + 1) { continue; }
+            out.push(format!("{}\t{}", self.size(entry.size()), path));
+        }
+    }
+}
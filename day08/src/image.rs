@@ -0,0 +1,79 @@
+use crate::{Distances, Map};
+use std::io::Write;
+
+type Rgb = [u8; 3];
+
+// The colour marking the best spots on a scenic score heatmap.
+const BEST: Rgb = [0, 160, 255];
+
+// Write |pixels| as a binary PGM (grey) or PPM (colour) image, drawing each one as a |scale| by
+// |scale| square so small forests are big enough to look at.
+fn write(path: &str, pixels: &[Vec<Rgb>], grey: bool, scale: usize) -> std::io::Result<()> {
+    let rows = pixels.len();
+    let cols = pixels.first().map_or(0, Vec::len);
+    let magic = if grey { "P5" } else { "P6" };
+
+    let mut out = Vec::new();
+    write!(out, "{}\n{} {}\n255\n", magic, cols * scale, rows * scale)?;
+    for row in pixels {
+        for _ in 0..scale {
+            for pixel in row {
+                for _ in 0..scale {
+                    if grey { out.push(pixel[0]); } else { out.extend_from_slice(pixel); }
+                }
+            }
+        }
+    }
+    return std::fs::write(path, out);
+}
+
+// Tree heights in grey, from black for the shortest to white for the tallest.
+pub fn heights(path: &str, map: &Map, scale: usize) -> std::io::Result<()> {
+    let heights = map.trees.iter().flatten().map(|tree| tree.height);
+    let min = heights.clone().min().unwrap_or(0);
+    let range = (heights.max().unwrap_or(0) - min).max(1);
+
+    let pixels: Vec<Vec<Rgb>> = map.trees.iter()
+        .map(|row| row.iter()
+            .map(|tree| {
+                // Scaled in u128, since heights can be anywhere up to usize::MAX.
+                let level = ((tree.height - min) as u128 * 255 / range as u128) as u8;
+                [level; 3]
+            })
+            .collect())
+        .collect();
+    return write(path, &pixels, true, scale);
+}
+
+// Trees visible from outside the forest in white, hidden ones in black.
+pub fn visibility(path: &str, map: &Map, scale: usize) -> std::io::Result<()> {
+    let pixels: Vec<Vec<Rgb>> = map.trees.iter()
+        .map(|row| row.iter()
+            .map(|tree| if tree.visible { [255; 3] } else { [0; 3] })
+            .collect())
+        .collect();
+    return write(path, &pixels, true, scale);
+}
+
+// Go from black through red and yellow to white as |level| goes from 0 to 1.
+fn heat(level: f64) -> Rgb {
+    let channel = |from: f64| (((level * 3.0 - from).clamp(0.0, 1.0)) * 255.0).round() as u8;
+    return [channel(0.0), channel(1.0), channel(2.0)];
+}
+
+// Scenic scores as a heatmap, with the best spots marked in blue (unless every score is 0). Scores
+// span several orders of magnitude, so they're coloured by their logarithm.
+pub fn scores(path: &str, distances: &[Vec<Distances>], scale: usize) -> std::io::Result<()> {
+    let best = distances.iter().flatten().map(Distances::score).max().unwrap_or(0);
+    let top = (best as f64).ln_1p().max(f64::MIN_POSITIVE);
+
+    let pixels: Vec<Vec<Rgb>> = distances.iter()
+        .map(|row| row.iter()
+            .map(|d| match d.score() {
+                score if score == best && best > 0 => BEST,
+                score => heat((score as f64).ln_1p() / top),
+            })
+            .collect())
+        .collect();
+    return write(path, &pixels, false, scale);
+}
@@ -1,3 +1,9 @@
+#![allow(clippy::needless_return)]
+
+mod image;
+mod placement;
+mod sight;
+
 #[derive(Debug, Clone, Copy)]
 struct Tree {
     height: usize,
@@ -6,15 +12,93 @@ struct Tree {
 
 impl Tree {
     fn new(height: usize) -> Self {
-        return Tree{height: height, visible: false};
+        return Tree{height, visible: false};
     }
 }
 
+// How many trees can be seen from a tree in each direction, up to and including the first one at
+// least as tall, or the edge.
+#[derive(Debug, Default, Clone, Copy)]
+struct Distances {
+    up: usize,
+    down: usize,
+    left: usize,
+    right: usize,
+}
+
+impl Distances {
+    fn score(&self) -> usize {
+        return self.up * self.down * self.left * self.right;
+    }
+}
+
+// For each tree in |trees|, how far it can see looking back towards the first one.
+//
+// |blockers| holds the trees that could still block the view of a later tree, from nearest to
+// furthest; each is taller than every tree between it and the one just looked at, so every tree is
+// pushed and popped at most once and the whole line takes linear time.
+fn look_back<'a>(trees: impl Iterator<Item = &'a Tree>) -> Vec<usize> {
+    let mut ret = Vec::new();
+    let mut blockers: Vec<(usize, usize)> = Vec::new();
+
+    for (i, tree) in trees.enumerate() {
+        while blockers.last().is_some_and(|&(_, height)| height < tree.height) {
+            blockers.pop();
+        }
+        ret.push(match blockers.last() {
+            Some(&(j, _)) => i - j,
+            None => i,
+        });
+        blockers.push((i, tree.height));
+    }
+    return ret;
+}
+
 #[derive(Debug)]
 struct Map {
+    // Every row has the same number of trees.
     trees: Vec<Vec<Tree>>,
 }
 
+// Read a grid of heights, one row per line. Rows are either written as digits, like "30373", or as
+// numbers separated by commas or whitespace, like "3, 0, 3, 7, 3" or "12 40 7", which allows
+// heights above 9. Blank lines at the end are ignored.
+fn parse_heights(lines: &[String]) -> Result<Vec<Vec<usize>>, String> {
+    let end = lines.iter().rposition(|line| !line.trim().is_empty()).map_or(0, |i| i + 1);
+    let lines = &lines[..end];
+    if lines.is_empty() { return Err("there are no trees".to_owned()); }
+
+    let separated = lines.iter()
+        .any(|line| line.trim().contains(|c: char| c == ',' || c.is_whitespace()));
+
+    let mut ret: Vec<Vec<usize>> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let row = if separated {
+            line.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|word| !word.is_empty())
+                .map(|word| word.parse::<usize>()
+                    .map_err(|_| format!("line {}: '{}' isn't a height", i + 1, word)))
+                .collect::<Result<Vec<usize>, String>>()?
+        } else {
+            line.chars()
+                .map(|c| c.to_digit(10)
+                    .map(|d| d as usize)
+                    .ok_or_else(|| format!("line {}: '{}' isn't a height", i + 1, c)))
+                .collect::<Result<Vec<usize>, String>>()?
+        };
+
+        if row.is_empty() { return Err(format!("line {}: there are no trees", i + 1)); }
+        if let Some(first) = ret.first() {
+            if row.len() != first.len() {
+                return Err(format!("line {}: there are {} trees, but line 1 has {}",
+                                   i + 1, row.len(), first.len()));
+            }
+        }
+        ret.push(row);
+    }
+    return Ok(ret);
+}
+
 impl Map {
     fn new(heights: Vec<Vec<usize>>) -> Self {
         fn to_trees(row: Vec<usize>) -> Vec<Tree> {
@@ -27,7 +111,15 @@ impl Map {
             .map(to_trees)
             .collect();
 
-        return Map{trees: trees};
+        return Map{trees};
+    }
+
+    fn rows(&self) -> usize {
+        return self.trees.len();
+    }
+
+    fn cols(&self) -> usize {
+        return self.trees.first().map_or(0, Vec::len);
     }
 
     fn print_map(&self) {
@@ -39,7 +131,7 @@ impl Map {
                 };
                 print!("{}", msg);
             }
-            println!("");
+            println!();
         }
     }
 
@@ -55,47 +147,36 @@ impl Map {
             .collect();
     }
 
-    fn get_score(&self, coords: (usize, usize)) -> usize {
-        fn get_score(trees: &mut dyn Iterator<Item=&Tree>) -> usize {
-            let tree = trees.next().unwrap();
-
-            let mut len = 0;
-            let mut tallest = -1i32;
+    // How far every tree can see in each direction, indexed like |trees|.
+    fn viewing_distances(&self) -> Vec<Vec<Distances>> {
+        let mut ret = vec![vec![Distances::default(); self.cols()]; self.rows()];
 
-            for (i, cur) in trees.enumerate() {
-                len = i;
-                tallest = cur.height as i32;
-
-                if tallest >= tree.height as i32 { break; }
+        for (row, trees) in self.trees.iter().enumerate() {
+            let left = look_back(trees.iter());
+            let right = look_back(trees.iter().rev());
+            for (col, distances) in ret[row].iter_mut().enumerate() {
+                distances.left = left[col];
+                distances.right = right[self.cols() - 1 - col];
+            }
+        }
+        for col in 0..self.cols() {
+            let trees = self.get_column(col);
+            let up = look_back(trees.iter());
+            let down = look_back(trees.iter().rev());
+            for (row, distances) in ret.iter_mut().enumerate() {
+                distances[col].up = up[row];
+                distances[col].down = down[self.rows() - 1 - row];
             }
-
-            if tallest < 0i32 { return 0; }
-            return len + 1;
         }
-
-        let (row, col) = coords;
-
-        let row_trees = &self.trees[row];
-        let from_right = get_score(&mut row_trees[..=col].iter().rev());
-        let from_left = get_score(&mut row_trees[col..].iter());
-
-        let col_trees = self.get_column(col);
-        let from_bottom = get_score(&mut col_trees[..=row].iter().rev());
-        let from_top = get_score(&mut col_trees[row..].iter());
-
-        let ret = from_right * from_left * from_bottom * from_top;
-        println!("{:?}: up {} down {} left {} right {} total {}",
-                 coords, from_bottom, from_top, from_right, from_left, ret);
         return ret;
     }
 
     fn update_visibilities(&mut self) {
         fn mark_visibles(trees: &mut dyn Iterator<Item=&mut Tree>) {
-            let mut prev_max = -1i32;
+            let mut prev_max: Option<usize> = None;
             for tree in trees {
-                let height: i32 = tree.height.try_into().unwrap();
-                if height <= prev_max.try_into().unwrap() { continue; }
-                prev_max = height;
+                if prev_max.is_some_and(|max| tree.height <= max) { continue; }
+                prev_max = Some(tree.height);
                 tree.visible = true;
             }
         }
@@ -104,51 +185,213 @@ impl Map {
             mark_visibles(&mut row.iter_mut());
             mark_visibles(&mut row.iter_mut().rev());
         }
-        for col in 0..self.trees.len() {
+        for col in 0..self.cols() {
             mark_visibles(&mut self.get_column_mut(col).into_iter());
             mark_visibles(&mut self.get_column_mut(col).into_iter().rev());
         }
     }
 }
 
-fn cartesian_product(len: usize) -> Vec<(usize, usize)> {
-    let mut ret = Vec::new();
+// Where to look out from for --from and --from-point.
+#[derive(Clone, Copy)]
+enum Lookout {
+    Tree(usize, usize),
+    Point(sight::Viewpoint),
+}
 
-    for i in 0..len {
-        for j in 0..len {
-            ret.push((i, j));
+struct Options {
+    lookout: Option<Lookout>,
+    // List the coordinates of every tree that can be seen from the lookout.
+    list: bool,
+    // Where to draw the heights, the trees visible from outside, and the scenic scores, and how
+    // many pixels across to draw each tree.
+    heights_image: Option<String>,
+    visibility_image: Option<String>,
+    scores_image: Option<String>,
+    scale: usize,
+    // List the best treehouse spots instead: how many, and which trees are allowed.
+    top: Option<usize>,
+    area: Option<placement::Area>,
+    min_height: Option<usize>,
+}
+
+impl Options {
+    fn parse(args: impl Iterator<Item = String>) -> Self {
+        let mut ret = Options{
+            lookout: None,
+            list: false,
+            heights_image: None,
+            visibility_image: None,
+            scores_image: None,
+            scale: 1,
+            top: None,
+            area: None,
+            min_height: None,
+        };
+        let mut args = args.skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--from" => {
+                    let text = args.next().expect("--from needs ROW,COL");
+                    let (row, col) = text.split_once(',').expect("--from needs ROW,COL");
+                    let row = row.trim().parse::<usize>().expect("--from needs a row number");
+                    let col = col.trim().parse::<usize>().expect("--from needs a column number");
+                    ret.lookout = Some(Lookout::Tree(row, col));
+                }
+                "--from-point" => {
+                    let text = args.next().expect("--from-point needs ROW,COL,HEIGHT");
+                    let point = sight::Viewpoint::parse(&text)
+                        .unwrap_or_else(|e| panic!("bad --from-point: {}", e));
+                    ret.lookout = Some(Lookout::Point(point));
+                }
+                "--list" => ret.list = true,
+                "--heights-image" => {
+                    ret.heights_image = Some(args.next().expect("--heights-image needs a file"));
+                }
+                "--visibility-image" => {
+                    let path = args.next().expect("--visibility-image needs a file");
+                    ret.visibility_image = Some(path);
+                }
+                "--scores-image" => {
+                    ret.scores_image = Some(args.next().expect("--scores-image needs a file"));
+                }
+                "--scale" => {
+                    ret.scale = args.next()
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .filter(|&n| n > 0)
+                        .expect("--scale needs a positive number");
+                }
+                "--top" => {
+                    let count = args.next()
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .filter(|&n| n > 0)
+                        .expect("--top needs a positive number");
+                    ret.top = Some(count);
+                }
+                "--within" => {
+                    let text = args.next().expect("--within needs TOP,LEFT,BOTTOM,RIGHT");
+                    let area = placement::Area::parse(&text)
+                        .unwrap_or_else(|e| panic!("bad --within: {}", e));
+                    ret.area = Some(area);
+                }
+                "--min-height" => {
+                    let height = args.next().expect("--min-height needs a height");
+                    let height = height.parse::<usize>().expect("--min-height should be a height");
+                    ret.min_height = Some(height);
+                }
+                _ => panic!("Unknown argument {}", arg),
+            }
         }
+
+        return ret;
     }
+}
 
-    return ret;
+fn print_viewshed(map: &Map, lookout: &Lookout, list: bool) {
+    let from = match *lookout {
+        Lookout::Tree(row, col) => {
+            if row >= map.rows() || col >= map.cols() {
+                eprintln!("there's no tree at {},{}; the forest is {} by {}",
+                          row, col, map.rows(), map.cols());
+                std::process::exit(1);
+            }
+            sight::Viewpoint::tree(map, row, col)
+        }
+        Lookout::Point(point) => point,
+    };
+    let viewshed = sight::viewshed(map, from);
+
+    for (row, cols) in viewshed.visible.iter().enumerate() {
+        for (col, &visible) in cols.iter().enumerate() {
+            let msg = match (visible, *lookout) {
+                (_, Lookout::Tree(r, c)) if (r, c) == (row, col) => '@',
+                (true, _) => 'x',
+                (false, _) => ' ',
+            };
+            print!("{}", msg);
+        }
+        println!();
+    }
+
+    let trees = viewshed.trees();
+    println!("{} trees can be seen from {},{} at height {}.",
+             trees.len(), from.row, from.col, from.height);
+    if list {
+        for (row, col) in trees { println!("{},{}", row, col); }
+    }
+}
+
+fn print_spots(map: &Map, distances: &[Vec<Distances>], options: &Options) {
+    let constraints = placement::Constraints{
+        area: options.area,
+        min_height: options.min_height.unwrap_or(0),
+    };
+    let spots = placement::best(map, distances, &constraints, options.top.unwrap_or(1));
+    if spots.is_empty() {
+        println!("no tree meets the constraints.");
+        return;
+    }
+
+    // Spots that tie share a rank.
+    let mut rank = 0;
+    for (i, spot) in spots.iter().enumerate() {
+        if i == 0 || spot.distances.score() != spots[i - 1].distances.score() { rank = i + 1; }
+        let d = &spot.distances;
+        println!("{}. {},{} (height {}): score {} (up {}, down {}, left {}, right {})",
+                 rank, spot.row, spot.col, spot.height, d.score(), d.up, d.down, d.left, d.right);
+    }
 }
 
 fn main() {
-    let heights = std::io::stdin()
+    let options = Options::parse(std::env::args());
+
+    let lines = std::io::stdin()
         .lines()
         .map(Result::unwrap)
-        .map(String::into_bytes)
-        .map(|bytes| {
-            bytes.into_iter()
-                .map(|c| c - ('0' as u8))
-                .map(usize::from)
-                .collect()
-        })
-        .collect();
+        .collect::<Vec<String>>();
+    let heights = parse_heights(&lines).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
 
     let mut map = Map::new(heights);
+    if let Some(lookout) = &options.lookout {
+        print_viewshed(&map, lookout, options.list);
+        return;
+    }
+
     map.update_visibilities();
     let visible_trees = map.trees.iter()
         .flatten()
         .filter(|i| i.visible)
         .count();
 
-    let best_spot = cartesian_product(map.trees.len())
+    let distances = map.viewing_distances();
+    if options.top.is_some() || options.area.is_some() || options.min_height.is_some() {
+        print_spots(&map, &distances, &options);
+        return;
+    }
+
+    let best_spot = distances
         .iter()
-        .map(|coords| map.get_score(*coords))
+        .flatten()
+        .map(Distances::score)
         .max()
         .unwrap();
 
+    fn draw(path: &Option<String>, draw: impl FnOnce(&str) -> std::io::Result<()>) {
+        let Some(path) = path else { return; };
+        if let Err(e) = draw(path) {
+            eprintln!("can't write {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+    let scale = options.scale;
+    draw(&options.heights_image, |path| image::heights(path, &map, scale));
+    draw(&options.visibility_image, |path| image::visibility(path, &map, scale));
+    draw(&options.scores_image, |path| image::scores(path, &distances, scale));
+
     map.print_map();
     println!("there are {} visible trees.", visible_trees);
     println!("the best spot has a score of {}.", best_spot);
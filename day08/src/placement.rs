@@ -0,0 +1,69 @@
+use crate::{Distances, Map};
+
+// A tree that could hold a treehouse, and how far it can see each way.
+pub struct Spot {
+    pub row: usize,
+    pub col: usize,
+    pub height: usize,
+    pub distances: Distances,
+}
+
+// Rows and columns from the first corner to the second, inclusive.
+#[derive(Debug, Clone, Copy)]
+pub struct Area {
+    pub top: usize,
+    pub left: usize,
+    pub bottom: usize,
+    pub right: usize,
+}
+
+impl Area {
+    // Parse "TOP,LEFT,BOTTOM,RIGHT".
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let parts = text.split(',')
+            .map(|part| part.trim().parse::<usize>()
+                .map_err(|_| format!("'{}' isn't a row or column", part)))
+            .collect::<Result<Vec<usize>, String>>()?;
+        let [top, left, bottom, right] = parts[..] else {
+            return Err(format!("expected TOP,LEFT,BOTTOM,RIGHT, found '{}'", text));
+        };
+        if top > bottom || left > right {
+            return Err(format!("{} has its corners the wrong way round", text));
+        }
+        return Ok(Area{top, left, bottom, right});
+    }
+
+    fn contains(&self, row: usize, col: usize) -> bool {
+        return (self.top..=self.bottom).contains(&row) && (self.left..=self.right).contains(&col);
+    }
+}
+
+// Which trees are allowed to hold a treehouse.
+pub struct Constraints {
+    pub area: Option<Area>,
+    pub min_height: usize,
+}
+
+// The |count| spots (at least one) with the best scenic scores that meet |constraints|, best first,
+// along with any more that tie with the last of them. Spots with the same score come in reading
+// order.
+pub fn best(
+    map: &Map, distances: &[Vec<Distances>], constraints: &Constraints, count: usize,
+) -> Vec<Spot> {
+    let mut spots = Vec::new();
+    for (row, trees) in map.trees.iter().enumerate() {
+        for (col, tree) in trees.iter().enumerate() {
+            if tree.height < constraints.min_height { continue; }
+            if constraints.area.is_some_and(|area| !area.contains(row, col)) { continue; }
+            spots.push(Spot{row, col, height: tree.height, distances: distances[row][col]});
+        }
+    }
+
+    // Sorting is stable, so ties stay in reading order.
+    spots.sort_by_key(|spot| std::cmp::Reverse(spot.distances.score()));
+    if let Some(last) = spots.get(count - 1).map(|spot| spot.distances.score()) {
+        let end = spots.iter().rposition(|spot| spot.distances.score() == last).unwrap();
+        spots.truncate(end + 1);
+    }
+    return spots;
+}
@@ -0,0 +1,112 @@
+use crate::Map;
+
+// Somewhere to look out from. Rows and columns are measured between tree centres, so the tree at
+// row 2, column 3 stands at (2.0, 3.0); a viewpoint can be anywhere, including outside the forest.
+#[derive(Debug, Clone, Copy)]
+pub struct Viewpoint {
+    pub row: f64,
+    pub col: f64,
+    pub height: f64,
+}
+
+impl Viewpoint {
+    // The top of the tree at |row|, |col|.
+    pub fn tree(map: &Map, row: usize, col: usize) -> Self {
+        let height = map.trees[row][col].height as f64;
+        return Viewpoint{row: row as f64, col: col as f64, height};
+    }
+
+    // Parse "ROW,COL,HEIGHT".
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let parts = text.split(',')
+            .map(|part| part.trim().parse::<f64>()
+                .map_err(|_| format!("'{}' isn't a number", part)))
+            .collect::<Result<Vec<f64>, String>>()?;
+        let [row, col, height] = parts[..] else {
+            return Err(format!("expected ROW,COL,HEIGHT, found '{}'", text));
+        };
+        return Ok(Viewpoint{row, col, height});
+    }
+}
+
+// Which trees can be seen from a viewpoint, indexed like |Map::trees|.
+pub struct Viewshed {
+    pub visible: Vec<Vec<bool>>,
+}
+
+impl Viewshed {
+    // Every visible tree, as (row, col), in reading order.
+    pub fn trees(&self) -> Vec<(usize, usize)> {
+        let mut ret = Vec::new();
+        for (row, cols) in self.visible.iter().enumerate() {
+            for (col, &visible) in cols.iter().enumerate() {
+                if visible { ret.push((row, col)); }
+            }
+        }
+        return ret;
+    }
+}
+
+// Whether the top of the tree at |row|, |col| can be seen from |from|.
+//
+// The forest between is treated as a surface through the tops of the trees: wherever the sightline
+// crosses a row or column of trees, the height there is interpolated between the two trees it
+// passes between. The tree is hidden if that surface comes up to the sightline anywhere.
+//
+// This isn't the puzzle's rule, even along a row or column: the puzzle looks out level from the
+// viewpoint, so a tree at least as tall as the viewpoint blocks everything behind it. Here the
+// sightline slopes up towards taller trees, so from a tree of height 3, the 9 in "3 3 9" is seen
+// over the middle tree.
+fn can_see(map: &Map, from: Viewpoint, row: usize, col: usize) -> bool {
+    let target = map.trees[row][col].height as f64;
+    let (d_row, d_col) = (row as f64 - from.row, col as f64 - from.col);
+
+    // The height of the forest at a point on row |r| between columns, or column |c| between rows.
+    let along_row = |r: usize, c: f64| {
+        let (left, right) = (c.floor() as usize, c.ceil() as usize);
+        let (a, b) = (map.trees[r][left].height as f64, map.trees[r][right].height as f64);
+        return a + (b - a) * (c - c.floor());
+    };
+    let along_col = |r: f64, c: usize| {
+        let (top, bottom) = (r.floor() as usize, r.ceil() as usize);
+        let (a, b) = (map.trees[top][c].height as f64, map.trees[bottom][c].height as f64);
+        return a + (b - a) * (r - r.floor());
+    };
+    // Whether the forest reaches the sightline |t| of the way to the target.
+    let blocked = |t: f64, forest: f64| {
+        return t > 1e-9 && t < 1.0 - 1e-9 && forest >= from.height + (target - from.height) * t;
+    };
+    let rows = (map.rows() - 1) as f64;
+    let cols = (map.cols() - 1) as f64;
+
+    // Every column of trees the sightline crosses.
+    if d_col != 0.0 {
+        let (lo, hi) = (from.col.min(col as f64), from.col.max(col as f64));
+        for c in (lo.ceil().max(0.0) as usize)..=(hi.floor().min(cols) as usize) {
+            let t = (c as f64 - from.col) / d_col;
+            let r = from.row + t * d_row;
+            if !(0.0..=rows).contains(&r) { continue; }
+            if blocked(t, along_col(r, c)) { return false; }
+        }
+    }
+    // And every row.
+    if d_row != 0.0 {
+        let (lo, hi) = (from.row.min(row as f64), from.row.max(row as f64));
+        for r in (lo.ceil().max(0.0) as usize)..=(hi.floor().min(rows) as usize) {
+            let t = (r as f64 - from.row) / d_row;
+            let c = from.col + t * d_col;
+            if !(0.0..=cols).contains(&c) { continue; }
+            if blocked(t, along_row(r, c)) { return false; }
+        }
+    }
+    return true;
+}
+
+// Work out which trees can be seen from |from|. A tree standing exactly at the viewpoint counts as
+// visible.
+pub fn viewshed(map: &Map, from: Viewpoint) -> Viewshed {
+    let visible = (0..map.rows())
+        .map(|row| (0..map.cols()).map(|col| can_see(map, from, row, col)).collect())
+        .collect();
+    return Viewshed{visible};
+}
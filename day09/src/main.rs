@@ -1,10 +1,11 @@
+#![allow(clippy::needless_return)]
+
 use std::collections::HashSet;
 
 type Coord = (i32, i32);
 
 #[derive(Clone, Copy, Debug)]
 enum Direction {
-    Unspec,
     Up,
     Down,
     Left,
@@ -19,19 +20,13 @@ struct Move {
 
 impl Move {
     fn new(direction: Direction, distance: usize) -> Self {
-        return Self{direction: direction, distance: distance};
-    }
-
-    fn next(&self) -> Self {
-        if self.distance == 0 { panic!("Can't decrement 0 vector"); }
-        return Self::new(self.direction, self.distance - 1);
+        return Self{direction, distance};
     }
 }
 
 fn move_coord(coord: Coord, direction: Direction) -> Coord {
     let (x, y) = coord;
     return match direction {
-        Direction::Unspec => panic!("Invalid direction!"),
         Direction::Up => (x, y + 1),
         Direction::Down => (x, y - 1),
         Direction::Left => (x - 1, y),
@@ -83,51 +78,58 @@ fn follow_coord(head: Coord, tail: Coord) -> Coord {
     };
 }
 
+// A rope made of knots, head first. Each knot only ever follows the one in front of it.
 struct Rope {
-    head: Coord,
-    tail: Option<Box<Rope>>,
+    knots: Vec<Coord>,
 }
 
 impl Rope {
     fn new(len: usize) -> Self {
-        let zero = (0, 0);
-        if len == 0 { return Self{head: zero, tail: None}; }
-        let cdr = Box::new(Self::new(len - 1));
-        return Self{head: zero, tail: Some(cdr)};
+        if len == 0 { panic!("A rope needs at least one knot"); }
+        return Self{knots: vec![(0, 0); len]};
     }
 
-    fn describe(&self, depth: usize) {
-        println!("{}:{:?}", depth, self.head);
-        if self.tail.is_none() { return; }
-        return self.tail.as_ref().unwrap().describe(depth + 1);
+    // Move the head one step, and pull the rest of the rope along behind it.
+    fn step(&mut self, direction: Direction) {
+        self.knots[0] = move_coord(self.knots[0], direction);
+        for i in 1..self.knots.len() {
+            let moved = follow_coord(self.knots[i - 1], self.knots[i]);
+            // If this knot stays put, so does everything behind it.
+            if moved == self.knots[i] { break; }
+            self.knots[i] = moved;
+        }
     }
+}
 
-    fn follow_head(&mut self, head: Coord) -> Coord {
-        self.head = follow_coord(head, self.head);
-        if self.tail.is_none() { return self.head; }
-        return self.tail.as_mut()
-            .unwrap()
-            .follow_head(self.head);
-    }
+struct Options {
+    // How many knots each rope has, head included.
+    lengths: Vec<usize>,
+}
 
-    // Assumes that this has a tail, otherwise it will crash.
-    fn apply_move(&mut self, m: Move, visited: &mut HashSet<Coord>) {
-        if m.distance == 0 { return; }
-        self.head = move_coord(self.head, m.direction);
-        let tail_pos = self.tail.as_mut()
-            .unwrap()
-            .follow_head(self.head);
-        visited.insert(tail_pos);
-        return self.apply_move(m.next(), visited);
+impl Options {
+    fn parse(args: impl Iterator<Item = String>) -> Self {
+        let mut ret = Options{lengths: Vec::new()};
+        for arg in args.skip(1) {
+            match arg.parse::<usize>() {
+                Ok(len) if len > 0 => ret.lengths.push(len),
+                _ => panic!("Unknown argument {}", arg),
+            }
+        }
+
+        // The puzzle's two ropes: a head and a tail, then ten knots.
+        if ret.lengths.is_empty() { ret.lengths = vec![2, 10]; }
+        return ret;
     }
 }
 
 fn main() {
+    let options = Options::parse(std::env::args());
+
     let moves: Vec<Move> = std::io::stdin()
         .lines()
         .map(Result::unwrap)
         .map(|line| -> Move {
-            let parts: Vec<&str> = line.split(" ").collect();
+            let parts: Vec<&str> = line.split(' ').collect();
             let direction = match parts[0] {
                 "R" => Direction::Right,
                 "L" => Direction::Left,
@@ -140,16 +142,20 @@ fn main() {
         })
         .collect();
 
-    let mut short_rope = Rope::new(1);
-    let mut short_visited = HashSet::new();
-
-    let mut long_rope = Rope::new(9);
-    let mut long_visited = HashSet::new();
+    // The knots of a rope don't depend on the ones behind them, so every shorter rope is the front
+    // of the longest one, and a single rope can stand in for all of them.
+    let mut rope = Rope::new(*options.lengths.iter().max().unwrap());
+    let mut visited: Vec<HashSet<Coord>> = vec![HashSet::from([(0, 0)]); options.lengths.len()];
 
     for m in moves {
-        long_rope.apply_move(m, &mut long_visited);
-        short_rope.apply_move(m, &mut short_visited);
+        for _ in 0..m.distance {
+            rope.step(m.direction);
+            for (len, visited) in options.lengths.iter().zip(&mut visited) {
+                visited.insert(rope.knots[len - 1]);
+            }
+        }
+    }
+    for (len, visited) in options.lengths.iter().zip(&visited) {
+        println!("{} knots: {} spots visited", len, visited.len());
     }
-    println!("short spots visited: {}", short_visited.len());
-    println!("long spots visited: {}", long_visited.len());
 }